@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
 pub struct AssignVal {
     pub base: f32,
     pub excess: f32,
@@ -134,6 +134,13 @@ pub fn key_pos(x: usize, y: usize) -> KeyPoint<usize, IndexSpace> {
     }
 }
 
+// Label of the control points of a curve. One off-curve point between two on-curve points makes
+// a quadratic Bezier and two make a cubic one. Control points are not allocation boundaries,
+// they are placed relative to the on-curve values around them.
+pub const OFF_CURVE: &str = "off";
+// Label of the points that smoothing keeps as corners.
+pub const CORNER: &str = "corner";
+
 impl<T, U> KeyPoint<T, U> {
     pub fn new(pos: Point2D<T, U>) -> Self {
         Self {
@@ -142,9 +149,61 @@ impl<T, U> KeyPoint<T, U> {
         }
     }
 
+    pub fn off_curve(pos: Point2D<T, U>) -> Self {
+        Self {
+            pos,
+            labels: vec![OFF_CURVE.to_string()],
+        }
+    }
+
     pub fn is_mark(&self) -> bool {
         self.labels.iter().any(|l| l == "mark")
     }
+
+    pub fn is_off_curve(&self) -> bool {
+        self.labels.iter().any(|l| l == OFF_CURVE)
+    }
+
+    // Whether the path passes through the point, so a curve is seen as its chord.
+    pub fn is_on_curve(&self) -> bool {
+        !self.is_mark() && !self.is_off_curve()
+    }
+}
+
+// Number of lines a curve is split into when measuring paths.
+pub const CURVE_STEPS: usize = 16;
+
+// The points of a path with each curve split into `steps` lines, without the marks.
+pub fn flatten_curves(kpoints: &[WorkKeyPoint], steps: usize) -> Vec<WorkPoint> {
+    let mut points: Vec<WorkPoint> = vec![];
+    let mut ctrls = vec![];
+    for kp in kpoints.iter().filter(|kp| !kp.is_mark()) {
+        if kp.is_off_curve() {
+            ctrls.push(kp.pos);
+            continue;
+        }
+        match points.last() {
+            Some(&start) if !ctrls.is_empty() => {
+                let hull: Vec<WorkPoint> = std::iter::once(start)
+                    .chain(ctrls.drain(..))
+                    .chain(std::iter::once(kp.pos))
+                    .collect();
+                points.extend((1..=steps).map(|i| {
+                    let t = i as f32 / steps as f32;
+                    let mut hull = hull.clone();
+                    while hull.len() > 1 {
+                        hull = hull.windows(2).map(|p| p[0].lerp(p[1], t)).collect();
+                    }
+                    hull[0]
+                }));
+            }
+            _ => {
+                ctrls.clear();
+                points.push(kp.pos);
+            }
+        }
+    }
+    points
 }
 
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
@@ -192,4 +251,30 @@ mod tests {
         kp = serde_json::from_value(json).unwrap();
         assert_eq!(kp.labels, vec!["tip"]);
     }
+
+    #[test]
+    fn test_flatten_curves() {
+        let mut mark = WorkKeyPoint::new(WorkPoint::new(0.5, 0.5));
+        mark.labels.push("mark".to_string());
+        let path = vec![
+            WorkKeyPoint::new(WorkPoint::new(0.0, 0.0)),
+            WorkKeyPoint::off_curve(WorkPoint::new(1.0, 0.0)),
+            mark,
+            WorkKeyPoint::new(WorkPoint::new(1.0, 1.0)),
+            WorkKeyPoint::new(WorkPoint::new(2.0, 1.0)),
+        ];
+        assert!(path[1].is_off_curve() && !path[1].is_on_curve());
+        assert!(!path[2].is_on_curve());
+
+        let points = flatten_curves(&path, 2);
+        assert_eq!(
+            points,
+            vec![
+                WorkPoint::new(0.0, 0.0),
+                WorkPoint::new(0.75, 0.25),
+                WorkPoint::new(1.0, 1.0),
+                WorkPoint::new(2.0, 1.0),
+            ]
+        );
+    }
 }
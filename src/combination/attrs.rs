@@ -50,6 +50,12 @@ impl CompAttrs {
             .and_then(|v| <T as CompAttrData>::from_sj_value(v.clone()))
     }
 
+    pub fn merge(&mut self, other: &CompAttrs) {
+        other.0.iter().for_each(|(k, v)| {
+            self.0.insert(k.clone(), v.clone());
+        });
+    }
+
     pub fn set<T: CompAttrData>(&mut self, attr: &T::Data)
     where
         <T as CompAttrData>::Data: serde::Serialize,
@@ -238,7 +244,7 @@ pub struct FixedAlloc;
 impl CompAttrData for FixedAlloc {
     type Data = DataHV<BTreeSet<usize>>;
     fn key() -> &'static str {
-        "fixed_Alloc"
+        "fixed_alloc"
     }
 }
 
@@ -1,10 +1,15 @@
 use crate::{
     base::*,
     combination::{StrucProto, StrucView, attrs, view},
-    construct::{CharTree, CstType},
-    service::algorithm as al,
+    config::SmoothSetting,
+    construct::{CharTree, CstError, CstType},
+    service::{algorithm as al, smooth::smooth_path},
 };
 use serde::{Deserialize, Serialize};
+use serde_json as sj;
+
+// Version of the serialized `StrucComb`, raised when the format changes.
+pub const COMB_VERSION: u32 = 1;
 
 #[derive(Serialize, Deserialize)]
 pub enum CompInfoData {
@@ -33,17 +38,38 @@ pub struct StrucCombInfo {
     pub cdata: CompInfoData,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GlyphMetrics {
+    // Width and height of the character box.
+    pub advance: DataHV<f32>,
+    // Side bearings between the character box and the ink box: `h` is [left, right] and `v` is
+    // [top, bottom].
+    pub bearings: DataHV<[f32; 2]>,
+    // Origin for vertical layout: the top center of the character box.
+    pub vert_origin: WorkPoint,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CompTree {
     pub name: String,
     pub tp: CstType,
     pub paths: Vec<Vec<WorkKeyPoint>>,
     pub children: Vec<CompTree>,
+    // The area allocated to the component, including its blanks.
+    pub alloc_box: WorkBox,
+    // Bounds of the strokes of the component and its children, `None` if it has none.
+    pub ink_box: Option<WorkBox>,
+    pub blanks: DataHV<[f32; 2]>,
+    // Only set on the root.
+    pub metrics: Option<GlyphMetrics>,
 }
 
+#[derive(Serialize, Deserialize)]
 pub enum CompData {
     Single {
         proto: StrucProto,
+        // Derived from `proto`, rebuilt after loading.
+        #[serde(skip)]
         view: StrucView,
         assigns: DataHV<Vec<AssignVal>>,
         level: DataHV<usize>,
@@ -65,6 +91,35 @@ pub struct CompIter<'a> {
     stack: Vec<usize>,
 }
 
+impl CompTree {
+    // Sets `ink_box` from the paths and the ink boxes of the children.
+    pub fn update_ink_box(&mut self) {
+        self.ink_box = self
+            .paths
+            .iter()
+            .flat_map(|path| flatten_curves(path, CURVE_STEPS))
+            .map(|p| WorkBox::new(p, p))
+            .chain(self.children.iter().filter_map(|c| c.ink_box))
+            .reduce(|a, b| WorkBox::new(a.min.min(b.min), a.max.max(b.max)));
+    }
+
+    pub fn set_metrics(&mut self, char_box: WorkBox) {
+        let advance = DataHV::new(char_box.width(), char_box.height());
+        let bearings = match self.ink_box {
+            Some(ink) => DataHV::new(
+                [ink.min.x - char_box.min.x, char_box.max.x - ink.max.x],
+                [ink.min.y - char_box.min.y, char_box.max.y - ink.max.y],
+            ),
+            None => DataHV::new([0.0, advance.h], [0.0, advance.v]),
+        };
+        self.metrics = Some(GlyphMetrics {
+            advance,
+            bearings,
+            vert_origin: WorkPoint::new(char_box.center().x, char_box.min.y),
+        });
+    }
+}
+
 impl<'a> CompIter<'a> {
     pub fn new(comb: &'a StrucComb) -> Self {
         Self {
@@ -115,6 +170,23 @@ impl<'a> std::iter::Iterator for CompIter<'a> {
     }
 }
 
+// Values of a node that should be identical: `pairs` index the list of `assign_vals`.
+struct SymmetricPairs {
+    path: String,
+    other: Option<String>,
+    pairs: Vec<[usize; 2]>,
+}
+
+// A mirror symmetric single component at `path` with different values on its two sides, or a
+// component differing from the identical sibling `other` before it.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Asymmetry {
+    pub path: String,
+    pub axis: Axis,
+    pub other: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct StrucComb {
     pub name: String,
     pub blanks: DataHV<[AssignVal; 2]>,
@@ -123,6 +195,63 @@ pub struct StrucComb {
 }
 
 impl StrucComb {
+    // Serializes the comb tagged with the version of the format.
+    pub fn to_saved(&self) -> sj::Result<sj::Value> {
+        let mut value = sj::to_value(self)?;
+        value["version"] = COMB_VERSION.into();
+        Ok(value)
+    }
+
+    // Loads a comb saved by `to_saved`. The assigns of single components must match the
+    // allocations of their prototypes, the views are rebuilt from the prototypes.
+    pub fn from_saved(value: sj::Value) -> Result<Self, CstError> {
+        let error = |msg: String| CstError::Load { msg };
+        match value.get("version").map(|v| v.as_u64()) {
+            Some(Some(version)) if version <= COMB_VERSION as u64 => {}
+            Some(_) => Err(error(format!("unsupported version `{}`", value["version"])))?,
+            None => Err(error("missing field `version`".to_string()))?,
+        }
+        let mut comb: Self = sj::from_value(value).map_err(|e| error(e.to_string()))?;
+        comb.restore("")?;
+        Ok(comb)
+    }
+
+    fn restore(&mut self, path: &str) -> Result<(), CstError> {
+        match &mut self.cdata {
+            CompData::Single {
+                proto,
+                view,
+                assigns,
+                ..
+            } => {
+                let allocs = proto.allocation_space();
+                for axis in Axis::list() {
+                    let (n, m) = (assigns.hv_get(axis).len(), allocs.hv_get(axis).len());
+                    if n != m {
+                        return Err(CstError::Load {
+                            msg: format!(
+                                "`{}` at `{path}` has {n} assigns in {axis:?} for {m} allocations",
+                                self.name
+                            ),
+                        });
+                    }
+                }
+                *view = StrucView::new(proto);
+            }
+            CompData::Scale { comps, .. } => {
+                for (i, c) in comps.iter_mut().enumerate() {
+                    c.restore(&Self::child_path(path, i))?;
+                }
+            }
+            CompData::Surround { .. } => {
+                return Err(CstError::Load {
+                    msg: format!("surround `{}` at `{path}` not supported", self.name),
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub fn new_single(name: String, proto: StrucProto) -> Self {
         Self {
             name,
@@ -161,6 +290,37 @@ impl StrucComb {
         CompIter::new(self)
     }
 
+    // `path` is the dot separated component indices from the root, e.g. `0.1`. Empty is the root.
+    pub fn node(&self, path: &str) -> Option<&StrucComb> {
+        path.split('.')
+            .filter(|s| !s.is_empty())
+            .try_fold(self, |comb, idx| match &comb.cdata {
+                CompData::Single { .. } => None,
+                CompData::Scale { comps, .. } | CompData::Surround { comps, .. } => {
+                    comps.get(idx.parse::<usize>().ok()?)
+                }
+            })
+    }
+
+    pub fn node_mut(&mut self, path: &str) -> Option<&mut StrucComb> {
+        path.split('.')
+            .filter(|s| !s.is_empty())
+            .try_fold(self, |comb, idx| match &mut comb.cdata {
+                CompData::Single { .. } => None,
+                CompData::Scale { comps, .. } | CompData::Surround { comps, .. } => {
+                    comps.get_mut(idx.parse::<usize>().ok()?)
+                }
+            })
+    }
+
+    // Path of the `index`th component of the node at `path`.
+    pub fn child_path(path: &str, index: usize) -> String {
+        match path.is_empty() {
+            true => index.to_string(),
+            false => format!("{path}.{index}"),
+        }
+    }
+
     pub fn get_comb_name(&self) -> String {
         match &self.cdata {
             CompData::Single { .. } => self.name.clone(),
@@ -275,6 +435,27 @@ impl StrucComb {
         }
     }
 
+    pub fn get_ink_area(&self, stroke: DataHV<f32>) -> f32 {
+        match &self.cdata {
+            CompData::Single { proto, assigns, .. } => proto.ink_area(
+                &assigns.map(|list| list.iter().map(|v| v.total()).collect()),
+                stroke,
+            ),
+            CompData::Scale { comps, .. } => comps.iter().map(|c| c.get_ink_area(stroke)).sum(),
+            CompData::Surround { .. } => todo!(), // surround
+        }
+    }
+
+    // Ink per area of the component, blanks included.
+    pub fn get_gray(&self, stroke: DataHV<f32>) -> f32 {
+        let area = self.get_assign_value(Axis::Horizontal, true).total()
+            * self.get_assign_value(Axis::Vertical, true).total();
+        match area == 0.0 {
+            true => 0.0,
+            false => self.get_ink_area(stroke) / area,
+        }
+    }
+
     pub fn get_assign_value(&self, axis: Axis, blank: bool) -> AssignVal {
         fn add(a: AssignVal, b: AssignVal) -> AssignVal {
             a + b
@@ -324,13 +505,15 @@ impl StrucComb {
         }
     }
 
-    pub fn get_comp_tree(&self) -> CompTree {
+    // The output paths, smoothed into curves with `smooth`.
+    pub fn get_comp_tree(&self, smooth: Option<&SmoothSetting>) -> CompTree {
         let mut start = self.get_char_box().min;
         let offset = self.get_white_area().unwrap();
         start.x += offset.h[0];
         start.y += offset.v[0];
 
-        let (tree, _) = self.get_paths_in(start);
+        let (mut tree, _) = self.get_paths_in(start, smooth);
+        tree.set_metrics(self.get_char_box());
         tree
     }
 
@@ -375,23 +558,36 @@ impl StrucComb {
         }
     }
 
-    fn get_paths_in(&self, start: WorkPoint) -> (CompTree, DataHV<f32>) {
+    fn get_paths_in(
+        &self,
+        start: WorkPoint,
+        smooth: Option<&SmoothSetting>,
+    ) -> (CompTree, DataHV<f32>) {
         let blanks = &self.blanks;
         let mut new_start =
             WorkPoint::new(start.x + blanks.h[0].total(), start.y + blanks.v[0].total());
         let mut size = DataHV::splat(0.0);
 
-        let tree = match &self.cdata {
+        let mut tree = match &self.cdata {
             CompData::Single { proto, assigns, .. } => {
                 let assigns = assigns.map(|assign| assign.iter().map(|av| av.total()).collect());
                 size = assigns.map(|assigns: &Vec<f32>| assigns.iter().sum::<f32>());
-                let paths = proto.get_paths(new_start, &assigns);
+                let mut paths = proto.get_paths(new_start, &assigns);
+                if let Some(setting) = smooth {
+                    for (path, stroke) in paths.iter_mut().zip(proto.strokes()) {
+                        *path = smooth_path(path, setting, &stroke);
+                    }
+                }
 
                 CompTree {
                     name: self.name.clone(),
                     tp: CstType::Single,
                     paths,
                     children: vec![],
+                    alloc_box: WorkBox::zero(),
+                    ink_box: None,
+                    blanks: Default::default(),
+                    metrics: None,
                 }
             }
             CompData::Scale {
@@ -405,7 +601,7 @@ impl StrucComb {
                 let children: Vec<_> = comps
                     .iter()
                     .map(|c| {
-                        let (c_tree, c_size) = c.get_paths_in(new_start);
+                        let (c_tree, c_size) = c.get_paths_in(new_start, smooth);
                         let advance = *c_size.hv_get(axis) + interval.next().unwrap_or_default();
                         *new_start.hv_get_mut(axis) += advance;
                         *size.hv_get_mut(axis) += advance;
@@ -420,6 +616,10 @@ impl StrucComb {
                     tp: CstType::Scale(axis),
                     paths: Default::default(),
                     children,
+                    ink_box: None,
+                    alloc_box: WorkBox::zero(),
+                    blanks: Default::default(),
+                    metrics: None,
                 }
             }
             CompData::Surround { .. } => todo!(), // surround
@@ -432,6 +632,9 @@ impl StrucComb {
                 .map(|ofs| ofs.total())
                 .sum::<f32>();
         }
+        tree.alloc_box = WorkBox::new(start, WorkPoint::new(start.x + size.h, start.y + size.v));
+        tree.update_ink_box();
+        tree.blanks = blanks.map(|data| data.map(|v| v.total()));
 
         (tree, size)
     }
@@ -715,6 +918,149 @@ impl StrucComb {
         }
     }
 
+    // All the assigned values in `axis` of the subtree in a fixed order.
+    fn assign_vals(&self, axis: Axis) -> Vec<AssignVal> {
+        let mut vals = self.blanks.hv_get(axis).to_vec();
+        match &self.cdata {
+            CompData::Single { assigns, .. } => vals.extend(assigns.hv_get(axis)),
+            CompData::Scale {
+                comps,
+                intervals_val,
+                ..
+            } => {
+                comps.iter().for_each(|c| vals.extend(c.assign_vals(axis)));
+                vals.extend(intervals_val);
+            }
+            CompData::Surround { .. } => todo!(), // surround
+        }
+        vals
+    }
+
+    fn assign_vals_mut(&mut self, axis: Axis) -> Vec<&mut AssignVal> {
+        let mut vals: Vec<_> = self.blanks.hv_get_mut(axis).iter_mut().collect();
+        match &mut self.cdata {
+            CompData::Single { assigns, .. } => vals.extend(assigns.hv_get_mut(axis)),
+            CompData::Scale {
+                comps,
+                intervals_val,
+                ..
+            } => {
+                comps
+                    .iter_mut()
+                    .for_each(|c| vals.extend(c.assign_vals_mut(axis)));
+                vals.extend(intervals_val);
+            }
+            CompData::Surround { .. } => todo!(), // surround
+        }
+        vals
+    }
+
+    // The pairs of values that should be identical: mirrored allocations of a symmetric single
+    // component, and the values of repeated identical siblings.
+    fn symmetric_pairs(&self, axis: Axis) -> Vec<SymmetricPairs> {
+        match &self.cdata {
+            CompData::Single { proto, assigns, .. } => {
+                let n = assigns.hv_get(axis).len();
+                match *proto.mirror_symmetry().hv_get(axis) {
+                    true => vec![SymmetricPairs {
+                        path: String::new(),
+                        other: None,
+                        pairs: (0..n / 2).map(|j| [j + 2, n + 1 - j]).collect(),
+                    }],
+                    false => vec![],
+                }
+            }
+            CompData::Scale { comps, .. } => {
+                let mut pairs = vec![];
+                let mut offset = 2;
+                let offsets: Vec<usize> = comps
+                    .iter()
+                    .map(|c| {
+                        let start = offset;
+                        offset += c.assign_vals(axis).len();
+                        start
+                    })
+                    .collect();
+                for (i, c) in comps.iter().enumerate() {
+                    pairs.extend(c.symmetric_pairs(axis).into_iter().map(|sp| {
+                        let join = |path: &str| match path.is_empty() {
+                            true => i.to_string(),
+                            false => format!("{i}.{path}"),
+                        };
+                        SymmetricPairs {
+                            path: join(&sp.path),
+                            other: sp.other.as_deref().map(join),
+                            pairs: sp
+                                .pairs
+                                .into_iter()
+                                .map(|p| p.map(|k| k + offsets[i]))
+                                .collect(),
+                        }
+                    }));
+                }
+                for (i, c1) in comps.iter().enumerate() {
+                    let Some(j) = (0..i).find(|&j| comps[j].get_comb_name() == c1.get_comb_name())
+                    else {
+                        continue;
+                    };
+                    let (v1, v2) = (comps[j].assign_vals(axis), c1.assign_vals(axis));
+                    if v1.len() == v2.len()
+                        && v1
+                            .iter()
+                            .zip(&v2)
+                            .all(|(a, b)| (a.base - b.base).abs() < al::NORMAL_OFFSET)
+                    {
+                        pairs.push(SymmetricPairs {
+                            path: i.to_string(),
+                            other: Some(j.to_string()),
+                            pairs: (0..v1.len())
+                                .map(|k| [offsets[j] + k, offsets[i] + k])
+                                .collect(),
+                        });
+                    }
+                }
+                pairs
+            }
+            CompData::Surround { .. } => todo!(), // surround
+        }
+    }
+
+    // Gives the symmetric allocations and identical siblings the same values, their mean.
+    pub fn symmetrize(&mut self) {
+        for axis in Axis::list() {
+            for sp in self.symmetric_pairs(axis) {
+                let mut vals = self.assign_vals_mut(axis);
+                for [a, b] in sp.pairs {
+                    let mean = (vals[a].total() + vals[b].total()) * 0.5;
+                    vals[a].uncheck_set(mean);
+                    vals[b].uncheck_set(mean);
+                }
+            }
+        }
+    }
+
+    // The symmetric values that differ.
+    pub fn asymmetries(&self) -> Vec<Asymmetry> {
+        Axis::list()
+            .into_iter()
+            .flat_map(|axis| {
+                let vals = self.assign_vals(axis);
+                self.symmetric_pairs(axis)
+                    .into_iter()
+                    .filter(move |sp| {
+                        sp.pairs.iter().any(|&[a, b]| {
+                            (vals[a].total() - vals[b].total()).abs() > al::NORMAL_OFFSET
+                        })
+                    })
+                    .map(move |sp| Asymmetry {
+                        path: sp.path,
+                        axis,
+                        other: sp.other,
+                    })
+            })
+            .collect()
+    }
+
     pub fn reassign_space(&mut self, mut new_val: f32, blank: bool, axis: Axis) {
         if blank {
             let old_assigns = self.get_assign_value(axis, false);
@@ -791,6 +1137,205 @@ mod tests {
         assert_eq!(names, ["1", "2", "3", "4", "5"]);
     }
 
+    #[test]
+    fn test_comb_serde() {
+        let proto = StrucProto::from(vec![
+            KeyPath::from([key_pos(0, 1), key_pos(4, 1)]),
+            KeyPath::from([key_pos(2, 0), key_pos(2, 4)]),
+        ]);
+        let mut single = StrucComb::new_single("十".to_string(), proto);
+        if let CompData::Single { assigns, level, .. } = &mut single.cdata {
+            *assigns = DataHV::new(
+                vec![AssignVal::new(0.1, 0.05); 2],
+                vec![AssignVal::new(0.1, 0.0), AssignVal::new(0.1, 0.3)],
+            );
+            *level = DataHV::new(1, 0);
+        }
+        single.blanks.h = [AssignVal::new(0.05, 0.01), AssignVal::default()];
+        let mut comb = StrucComb::new_complex(
+            "卄".to_string(),
+            CstType::Scale(Axis::Horizontal),
+            vec![
+                single,
+                StrucComb::new_single("一".to_string(), Default::default()),
+            ],
+        );
+        if let CompData::Scale {
+            intervals,
+            intervals_val,
+            ..
+        } = &mut comb.cdata
+        {
+            *intervals = vec![1];
+            *intervals_val = vec![AssignVal::new(0.1, 0.02)];
+        }
+        comb.set_white_area(&DataHV::splat([0.1, 0.2]));
+
+        let value = comb.to_saved().unwrap();
+        assert_eq!(value["version"], COMB_VERSION);
+        let loaded = StrucComb::from_saved(value.clone()).unwrap();
+        assert_eq!(loaded.to_saved().unwrap(), value);
+        assert_eq!(loaded.get_comb_name(), comb.get_comb_name());
+        assert_eq!(loaded.get_white_area(), Some(DataHV::splat([0.1, 0.2])));
+        let profiles = [&loaded, &comb].map(|c| {
+            c.node("0")
+                .unwrap()
+                .get_edge(Axis::Vertical, Side::Front, false)
+                .to_profile()
+        });
+        assert_eq!(profiles[0], profiles[1]);
+        assert_ne!(profiles[0].occupied(), 0);
+
+        let mut value = value;
+        value["cdata"]["Scale"]["comps"][0]["cdata"]["Single"]["assigns"]["h"]
+            .as_array_mut()
+            .unwrap()
+            .pop();
+        assert!(matches!(
+            StrucComb::from_saved(value.clone()),
+            Err(CstError::Load { msg }) if msg.contains("`十` at `0`")
+        ));
+        value["version"] = (COMB_VERSION + 1).into();
+        assert!(StrucComb::from_saved(value.clone()).is_err());
+        value.as_object_mut().unwrap().remove("version");
+        assert!(StrucComb::from_saved(value).is_err());
+
+        let surround = StrucComb::new_complex(
+            "同".to_string(),
+            CstType::Surround(DataHV::new(Section::Middle, Section::Start)),
+            vec![StrucComb::new_single("一".to_string(), Default::default())],
+        );
+        assert!(matches!(
+            StrucComb::from_saved(surround.to_saved().unwrap()),
+            Err(CstError::Load { msg }) if msg.contains("surround `同`")
+        ));
+    }
+
+    #[test]
+    fn test_comp_tree_boxes() {
+        let set_assigns = |comb: &mut StrucComb, h: Vec<f32>, v: Vec<f32>| {
+            if let CompData::Single { assigns, .. } = &mut comb.cdata {
+                let to_vals = |list: Vec<f32>| list.into_iter().map(|v| AssignVal::new(v, 0.0));
+                *assigns = DataHV::new(to_vals(h).collect(), to_vals(v).collect());
+            }
+        };
+        let mut left = StrucComb::new_single(
+            "十".to_string(),
+            StrucProto::from(vec![
+                KeyPath::from([key_pos(0, 1), key_pos(4, 1)]),
+                KeyPath::from([key_pos(2, 0), key_pos(2, 4)]),
+            ]),
+        );
+        set_assigns(&mut left, vec![0.1, 0.1], vec![0.2, 0.2]);
+        left.blanks.h[0] = AssignVal::new(0.05, 0.0);
+        let mut right = StrucComb::new_single(
+            "一".to_string(),
+            StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]),
+        );
+        set_assigns(&mut right, vec![0.3], vec![]);
+        right.blanks.v = [AssignVal::new(0.1, 0.0), AssignVal::new(0.3, 0.0)];
+        let mut comb = StrucComb::new_complex(
+            "卄".to_string(),
+            CstType::Scale(Axis::Horizontal),
+            vec![left, right],
+        );
+        if let CompData::Scale { intervals_val, .. } = &mut comb.cdata {
+            *intervals_val = vec![AssignVal::new(0.1, 0.0)];
+        }
+        comb.set_white_area(&DataHV::new([0.1, 0.1], [0.2, 0.2]));
+
+        let approx = |b: WorkBox, min: (f32, f32), max: (f32, f32)| {
+            [
+                b.min.x - min.0,
+                b.min.y - min.1,
+                b.max.x - max.0,
+                b.max.y - max.1,
+            ]
+            .iter()
+            .all(|d| d.abs() < 1e-5)
+        };
+        let tree = comb.get_comp_tree(None);
+        assert!(approx(tree.alloc_box, (0.1, 0.2), (0.75, 0.6)));
+        assert!(approx(tree.ink_box.unwrap(), (0.15, 0.2), (0.75, 0.6)));
+        assert!(approx(tree.children[0].alloc_box, (0.1, 0.2), (0.35, 0.6)));
+        assert!(approx(
+            tree.children[0].ink_box.unwrap(),
+            (0.15, 0.2),
+            (0.35, 0.6)
+        ));
+        assert_eq!(tree.children[0].blanks.h, [0.05, 0.0]);
+        assert!(approx(tree.children[1].alloc_box, (0.45, 0.2), (0.75, 0.6)));
+        assert!(approx(
+            tree.children[1].ink_box.unwrap(),
+            (0.45, 0.3),
+            (0.75, 0.3)
+        ));
+        assert!(tree.children[0].metrics.is_none());
+
+        let metrics = tree.metrics.unwrap();
+        assert_eq!(metrics.advance, DataHV::splat(1.0));
+        assert!((metrics.bearings.h[0] - 0.15).abs() < 1e-5);
+        assert!((metrics.bearings.h[1] - 0.25).abs() < 1e-5);
+        assert!((metrics.bearings.v[0] - 0.2).abs() < 1e-5);
+        assert!((metrics.bearings.v[1] - 0.4).abs() < 1e-5);
+        assert_eq!(metrics.vert_origin, WorkPoint::new(0.5, 0.0));
+
+        let mut empty = StrucComb::new_single("一".to_string(), Default::default());
+        empty.set_white_area(&Default::default());
+        assert!(empty.get_comp_tree(None).ink_box.is_none());
+    }
+
+    #[test]
+    fn test_symmetrize() {
+        // 木 without the diagonals: a horizontal and a centered vertical.
+        let mu = |h: [f32; 2]| {
+            let proto = StrucProto::from(vec![
+                KeyPath::from([key_pos(0, 1), key_pos(4, 1)]),
+                KeyPath::from([key_pos(2, 0), key_pos(2, 4)]),
+            ]);
+            let mut comb = StrucComb::new_single("木".to_string(), proto);
+            if let CompData::Single { assigns, .. } = &mut comb.cdata {
+                *assigns = DataHV::new(
+                    h.iter().map(|&v| AssignVal::new(0.1, v - 0.1)).collect(),
+                    vec![AssignVal::new(0.1, 0.1), AssignVal::new(0.1, 0.5)],
+                );
+            }
+            comb
+        };
+        let mut comb = StrucComb::new_complex(
+            "林".to_string(),
+            CstType::Scale(Axis::Horizontal),
+            vec![mu([0.2, 0.22]), mu([0.25, 0.25])],
+        );
+        let paths: Vec<_> = comb
+            .asymmetries()
+            .into_iter()
+            .map(|asym| (asym.path, asym.other))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("0".to_string(), None),
+                ("1".to_string(), Some("0".to_string()))
+            ]
+        );
+
+        let total = comb.get_assign_value(Axis::Horizontal, true).total();
+        comb.symmetrize();
+        assert!(comb.asymmetries().is_empty());
+        assert!((comb.get_assign_value(Axis::Horizontal, true).total() - total).abs() < 1e-4);
+        let totals: Vec<Vec<f32>> = ["0", "1"]
+            .iter()
+            .map(|p| {
+                let vals = comb.node(p).unwrap().assign_vals(Axis::Horizontal);
+                vals.iter().map(|v| v.total()).collect()
+            })
+            .collect();
+        assert_eq!(totals[0], totals[1]);
+        let vals = comb.node("0").unwrap().assign_vals(Axis::Vertical);
+        assert!((vals[2].total() - 0.2).abs() < 1e-4);
+    }
+
     #[test]
     fn test_edge_sharpness() {
         use view::SharpnessModel;
@@ -812,6 +1357,15 @@ mod tests {
         assert_eq!(sharpness.v[0], 0.0);
         assert_eq!(sharpness.v[1], 1.0);
 
+        let edge = comb.get_edge(Axis::Horizontal, Side::Front, false);
+        assert_eq!(edge.sharpness(SharpnessModel::Coverage), 0.5);
+        assert_eq!(
+            edge.sharpness(SharpnessModel::Weighted { falloff: 1.0 }),
+            0.5
+        );
+        let edge = comb.get_edge(Axis::Vertical, Side::Back, false);
+        assert_eq!(edge.sharpness(SharpnessModel::Coverage), 1.0 / 3.0);
+
         let struc = StrucProto::from(vec![KeyPath::from([
             key_pos(0, 0),
             key_pos(2, 0),
@@ -830,5 +1384,23 @@ mod tests {
         assert_eq!(sharpness.h[1], 1.0);
         assert_eq!(sharpness.v[0], 1.0);
         assert_eq!(sharpness.v[1], 1.0);
+        assert_eq!(
+            comb.get_edge(Axis::Vertical, Side::Front, false)
+                .sharpness(SharpnessModel::Coverage),
+            1.0
+        );
+
+        let struc = StrucProto::from(vec![
+            KeyPath::from([key_pos(0, 0), key_pos(0, 4)]),
+            KeyPath::from([key_pos(4, 0), key_pos(0, 4)]),
+        ]);
+        let comb = StrucComb::new_single("name".to_string(), struc);
+        let edge = comb.get_edge(Axis::Horizontal, Side::Back, false);
+        assert_eq!(edge.sharpness(SharpnessModel::ZeroOne), 0.0);
+        assert_eq!(edge.sharpness(SharpnessModel::Coverage), 0.0);
+        assert_eq!(
+            edge.sharpness(SharpnessModel::Diagonal { weight: 0.5 }),
+            0.5
+        );
     }
 }
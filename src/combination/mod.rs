@@ -3,6 +3,6 @@ pub mod comb;
 pub mod struc;
 pub mod view;
 
-pub use comb::{CompData, CompTree, StrucComb};
+pub use comb::{Asymmetry, CompData, CompTree, GlyphMetrics, StrucComb};
 pub use struc::StrucProto;
 pub use view::{SharpnessModel, StrucView};
@@ -32,13 +32,16 @@ impl StrucProto {
             .iter()
             .filter(|path| !path.hide)
             .map(|path| {
-                path.kpoints
+                let kpoints: Vec<_> = path
+                    .kpoints
+                    .iter()
+                    .filter(|kp| !kp.is_off_curve())
+                    .collect();
+                kpoints
                     .windows(2)
                     .map(|pos| Direction::new(pos[0].pos, Some(pos[1].pos)).symbol())
                     .fold(
-                        String::with_capacity(
-                            path.kpoints.len().checked_sub(1).unwrap_or_default(),
-                        ),
+                        String::with_capacity(kpoints.len().saturating_sub(1)),
                         |mut strokes, s| {
                             strokes.push(s);
                             strokes
@@ -52,11 +55,14 @@ impl StrucProto {
         self.paths
             .iter()
             .fold(DataHV::<BTreeSet<usize>>::default(), |mut set, path| {
-                path.kpoints.iter().for_each(|p| {
-                    for axis in Axis::list() {
-                        set.hv_get_mut(axis).insert(*p.pos.hv_get(axis));
-                    }
-                });
+                path.kpoints
+                    .iter()
+                    .filter(|p| !p.is_off_curve())
+                    .for_each(|p| {
+                        for axis in Axis::list() {
+                            set.hv_get_mut(axis).insert(*p.pos.hv_get(axis));
+                        }
+                    });
                 set
             })
             .into_map(|set| set.into_iter().collect())
@@ -110,6 +116,40 @@ impl StrucProto {
             .into_map(|l| l.into_iter().filter(|n| *n != 0).collect())
     }
 
+    // Whether the visible paths mirror onto themselves across the middle of each axis, with
+    // mirrored allocations too.
+    pub fn mirror_symmetry(&self) -> DataHV<bool> {
+        let paths: Vec<Vec<IndexPoint>> = self
+            .paths
+            .iter()
+            .filter(|path| !path.hide)
+            .map(|path| path.kpoints.iter().map(|kp| kp.pos).collect())
+            .collect();
+        let allocs = self.allocation_space();
+
+        Axis::hv().into_map(|axis| {
+            let values = paths.iter().flatten().map(|p| *p.hv_get(axis));
+            let (Some(min), Some(max)) = (values.clone().min(), values.max()) else {
+                return false;
+            };
+            let allocs = allocs.hv_get(axis);
+            allocs.iter().eq(allocs.iter().rev())
+                && paths.iter().all(|path| {
+                    let mirror: Vec<IndexPoint> = path
+                        .iter()
+                        .map(|p| {
+                            let mut p = *p;
+                            *p.hv_get_mut(axis) = min + max - *p.hv_get(axis);
+                            p
+                        })
+                        .collect();
+                    paths
+                        .iter()
+                        .any(|other| *other == mirror || other.iter().eq(mirror.iter().rev()))
+                })
+        })
+    }
+
     pub fn set_allocs_in_adjacency(&mut self, adjacency: DataHV<[bool; 2]>) {
         let mut allocs_proto = self.allocation_values();
         if let Some(ipa) = self.attrs.get::<attrs::InPlaceAllocs>() {
@@ -185,6 +225,10 @@ impl StrucProto {
                     .enumerate()
                     .collect()
             });
+        let slots: DataHV<BTreeMap<usize, f32>> = pos_to_alloc
+            .as_ref()
+            .zip(alloc_to_assign.as_ref())
+            .into_map(|(m1, m2)| m1.iter().map(|(v, i)| (*v, m2[i])).collect());
         let mut paths: Vec<_> = self
             .paths
             .iter()
@@ -197,9 +241,12 @@ impl StrucProto {
                         let pos = p
                             .pos
                             .to_hv_data()
-                            .zip(pos_to_alloc.as_ref())
-                            .zip(alloc_to_assign.as_ref())
-                            .into_map(|((v, m1), m2)| m2[&m1[&v]]);
+                            .zip(slots.as_ref())
+                            .zip(start.to_hv_data())
+                            .into_map(|((v, slots), start)| match p.is_off_curve() {
+                                true => curve_pos(slots, v, start),
+                                false => slots[&v],
+                            });
                         KeyPoint {
                             pos: WorkPoint::new(pos.h, pos.v),
                             labels: p.labels.clone(),
@@ -213,13 +260,36 @@ impl StrucProto {
 
         paths.iter_mut().for_each(|path| {
             if path.len() > 2 {
-                path.dedup_by_key(|p| p.pos);
+                path.dedup_by(|p1, p2| {
+                    p1.pos == p2.pos && !p1.is_off_curve() && !p2.is_off_curve()
+                });
             }
         });
 
         paths
     }
 
+    // Estimated ink of the paths drawn with `assigns`, the stroke width following the direction
+    // between `stroke.h` for horizontal and `stroke.v` for vertical segments.
+    pub fn ink_area(&self, assigns: &DataHV<Vec<f32>>, stroke: DataHV<f32>) -> f32 {
+        self.get_paths(WorkPoint::zero(), assigns)
+            .iter()
+            .map(|path| {
+                flatten_curves(path, CURVE_STEPS)
+                    .windows(2)
+                    .map(|p| {
+                        let (dx, dy) = ((p[1].x - p[0].x).abs(), (p[1].y - p[0].y).abs());
+                        if dx + dy == 0.0 {
+                            0.0
+                        } else {
+                            dx.hypot(dy) * (dx * stroke.h + dy * stroke.v) / (dx + dy)
+                        }
+                    })
+                    .sum::<f32>()
+            })
+            .sum()
+    }
+
     pub fn subarea_line_weight(
         &self,
         assigns: &DataHV<Vec<f32>>,
@@ -233,7 +303,7 @@ impl StrucProto {
             let iter = path
                 .kpoints
                 .iter()
-                .filter(|pos| !pos.is_mark())
+                .filter(|pos| pos.is_on_curve())
                 .map(|kp| IndexPoint::new(values_map.h[&kp.pos.x], values_map.v[&kp.pos.y]));
             iter.clone().zip(iter.skip(1)).for_each(|(p1, p2)| {
                 let min = p1.min(p2);
@@ -302,9 +372,47 @@ impl StrucProto {
     }
 }
 
+// Position of the control point value `v` from the positions of the on-curve values around it,
+// extending the outermost interval beyond them.
+fn curve_pos(slots: &BTreeMap<usize, f32>, v: usize, start: f32) -> f32 {
+    let pair: Vec<(&usize, &f32)> = match (slots.range(..=v).next_back(), slots.range(v..).next()) {
+        (Some(a), Some(b)) => vec![a, b],
+        (Some(_), None) => {
+            let mut pair: Vec<_> = slots.iter().rev().take(2).collect();
+            pair.reverse();
+            pair
+        }
+        (None, Some(_)) => slots.iter().take(2).collect(),
+        (None, None) => vec![],
+    };
+    match pair[..] {
+        [(&a, &pa), (&b, &pb)] if a != b => pa + (pb - pa) * (v as f32 - a as f32) / (b - a) as f32,
+        [(_, &p), ..] => p,
+        [] => start,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::combination::StrucView;
+
+    #[test]
+    fn test_mirror_symmetry() {
+        let proto = StrucProto::from(vec![
+            KeyPath::from([key_pos(0, 1), key_pos(4, 1)]),
+            KeyPath::from([key_pos(2, 0), key_pos(2, 3)]),
+            KeyPath::from([key_pos(1, 2), key_pos(0, 3)]),
+            KeyPath::from([key_pos(4, 3), key_pos(3, 2)]),
+        ]);
+        assert_eq!(proto.mirror_symmetry(), DataHV::new(true, false));
+
+        let proto = StrucProto::from(vec![
+            KeyPath::from([key_pos(0, 0), key_pos(2, 0), key_pos(1, 1)]),
+            KeyPath::from([key_pos(1, 1), key_pos(1, 3)]),
+        ]);
+        assert_eq!(proto.mirror_symmetry(), DataHV::new(false, false));
+    }
 
     #[test]
     fn test_values() {
@@ -395,6 +503,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_curve() {
+        // 丿 as a quadratic curve bending through the top right.
+        let struc = StrucProto::from(vec![KeyPath::from([
+            key_pos(2, 0),
+            KeyPoint::off_curve(IndexPoint::new(2, 2)),
+            key_pos(0, 4),
+        ])]);
+        assert_eq!(struc.strokes(), vec!["1".to_string()]);
+        assert_eq!(struc.allocation_space(), DataHV::new(vec![2], vec![4]));
+
+        let assigns = DataHV::new(vec![0.25], vec![0.5]);
+        let paths = struc.get_paths(WorkPoint::new(0.125, 0.125), &assigns);
+        assert_eq!(paths[0].len(), 3);
+        assert!(paths[0][1].is_off_curve());
+        assert_eq!(paths[0][1].pos, WorkPoint::new(0.375, 0.375));
+        assert_eq!(paths[0][2].pos, WorkPoint::new(0.125, 0.625));
+
+        // A control point outside the on-curve values follows the outermost interval.
+        let outer = StrucProto::from(vec![KeyPath::from([
+            key_pos(0, 0),
+            KeyPoint::off_curve(IndexPoint::new(3, 1)),
+            key_pos(2, 2),
+        ])]);
+        assert_eq!(outer.allocation_space(), DataHV::splat(vec![2]));
+        let paths = outer.get_paths(WorkPoint::zero(), &DataHV::splat(vec![0.5]));
+        assert_eq!(paths[0][1].pos, WorkPoint::new(0.75, 0.25));
+
+        let view = StrucView::new(&struc);
+        assert_eq!(
+            view.get_edge(Axis::Horizontal, Side::Front)
+                .to_profile()
+                .occupied(),
+            1
+        );
+        assert!(struc.ink_area(&assigns, DataHV::splat(0.1)) > 0.0);
+    }
+
     #[test]
     fn test_subarea_wline_eight() {
         let zero = DataHV::splat(0.0);
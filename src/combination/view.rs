@@ -1,13 +1,55 @@
 use crate::{base::*, combination::struc::StrucProto};
 
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// How much an edge is taken as black, from 0.0 to 1.0.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(tag = "model")]
 pub enum SharpnessModel {
+    // 1.0 if any stroke runs along the edge.
+    #[default]
     ZeroOne,
+    // Fraction of the edge covered by strokes running along it.
+    Coverage,
+    // Coverage weighted by `1 / (1 + falloff * d)`, `d` is the distance to the middle of the edge relative to half of its length.
+    Weighted {
+        falloff: f32,
+    },
+    // Coverage where diagonal strokes leaving the edge count as `weight`.
+    Diagonal {
+        weight: f32,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub struct Edge {
     axis: Axis,
     data: Vec<Option<Vec<ViewElement>>>,
+    // Alloc units from the edge to the first stroke inward, aligned with `data`.
+    depth: Vec<Option<usize>>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct EdgeProfile {
+    // Whether a stroke touches each unit of the edge.
+    pub occupancy: Vec<bool>,
+    // Counts of the elements on the edge by `ViewElement::symbol`.
+    pub directions: BTreeMap<char, usize>,
+    pub depth: Vec<Option<usize>>,
+    // `SharpnessModel::Coverage` of the edge.
+    pub coverage: f32,
+}
+
+impl EdgeProfile {
+    pub fn occupied(&self) -> usize {
+        self.occupancy.iter().filter(|b| **b).count()
+    }
+
+    // The deepest recess of the edge.
+    pub fn max_depth(&self) -> Option<usize> {
+        self.depth.iter().flatten().max().copied()
+    }
 }
 
 impl Edge {
@@ -73,17 +115,81 @@ impl Edge {
     }
 
     pub fn sharpness(&self, model: SharpnessModel) -> f32 {
-        match model {
-            SharpnessModel::ZeroOne => {
-                if self.contain_black() {
-                    1.0
-                } else {
-                    0.0
-                }
+        let zero_one = || match self.contain_black() {
+            true => 1.0,
+            false => 0.0,
+        };
+        if self.data.len() < 2 {
+            return zero_one();
+        }
+
+        let center = (self.data.len() - 1) as f32 / 2.0;
+        let (units, weights): (Vec<f32>, Vec<f32>) = match model {
+            SharpnessModel::ZeroOne => return zero_one(),
+            SharpnessModel::Coverage => (self.coverage(0.0), vec![]),
+            SharpnessModel::Diagonal { weight } => (self.coverage(weight), vec![]),
+            SharpnessModel::Weighted { falloff } => {
+                let units = self.coverage(0.0);
+                let weights = (0..units.len())
+                    .map(|k| 1.0 / (1.0 + falloff * ((k as f32 + 0.5 - center) / center).abs()))
+                    .collect();
+                (units, weights)
+            }
+        };
+
+        match weights.is_empty() {
+            true => units.iter().sum::<f32>() / units.len() as f32,
+            false => {
+                units
+                    .iter()
+                    .zip(weights.iter())
+                    .map(|(u, w)| u * w)
+                    .sum::<f32>()
+                    / weights.iter().sum::<f32>()
             }
         }
     }
 
+    // The black of each unit of the edge: 1.0 for strokes along it, `diagonal` for diagonal strokes.
+    fn coverage(&self, diagonal: f32) -> Vec<f32> {
+        use Direction::*;
+        use ViewElement::{D, Horizontal, Vertical};
+
+        let (straight, slant) = match self.axis {
+            Axis::Horizontal => (
+                [[D(Below), Vertical], [D(Above), Vertical]],
+                [[D(LeftBelow), D(RightBelow)], [D(LeftAbove), D(RightAbove)]],
+            ),
+            Axis::Vertical => (
+                [[D(Right), Horizontal], [D(Left), Horizontal]],
+                [[D(RightAbove), D(RightBelow)], [D(LeftAbove), D(LeftBelow)]],
+            ),
+        };
+        let has = |eles: &Vec<ViewElement>, list: &[ViewElement; 2]| {
+            eles.iter().any(|ele| list.contains(ele))
+        };
+
+        let points: Vec<(usize, &Vec<ViewElement>)> = self
+            .data
+            .iter()
+            .enumerate()
+            .filter_map(|(i, eles)| eles.as_ref().map(|eles| (i, eles)))
+            .collect();
+        let mut units = vec![0.0; self.data.len() - 1];
+        for pair in points.windows(2) {
+            let ((p, e1), (q, e2)) = (pair[0], pair[1]);
+            let val = if has(e1, &straight[0]) || has(e2, &straight[1]) {
+                1.0
+            } else if has(e1, &slant[0]) || has(e2, &slant[1]) {
+                diagonal
+            } else {
+                0.0
+            };
+            units[p..q].fill(val);
+        }
+        units
+    }
+
     pub fn connect(&mut self, mut other: Edge, space: usize) {
         if space == 0 {
             self.data
@@ -92,24 +198,60 @@ impl Edge {
                 .get_or_insert_default()
                 .append(other.data[0].take().get_or_insert_default());
             self.data.extend(other.data.into_iter().skip(1));
+
+            let last = self.depth.last_mut().unwrap();
+            *last = match (*last, other.depth[0]) {
+                (Some(d1), Some(d2)) => Some(d1.min(d2)),
+                (d1, d2) => d1.or(d2),
+            };
+            self.depth.extend(other.depth.into_iter().skip(1));
         } else {
             if space > 1 {
                 self.data.extend(vec![None; space - 1]);
+                self.depth.extend(vec![None; space - 1]);
             }
             self.data.append(&mut other.data);
+            self.depth.append(&mut other.depth);
         }
     }
 
     pub fn add(&mut self) {
         self.data.push(None);
+        self.depth.push(None);
     }
 
     pub fn add_head(&mut self) {
         self.data.insert(0, None);
+        self.depth.insert(0, None);
     }
 
     pub fn backspace(&mut self) {
         self.data.fill(None);
+        self.depth.fill(None);
+    }
+
+    pub fn to_profile(&self) -> EdgeProfile {
+        let mut directions = BTreeMap::new();
+        self.data
+            .iter()
+            .flatten()
+            .flatten()
+            .filter_map(|ele| ele.symbol())
+            .for_each(|c| *directions.entry(c).or_insert(0) += 1);
+
+        EdgeProfile {
+            occupancy: self
+                .data
+                .iter()
+                .map(|eles| {
+                    eles.as_ref()
+                        .is_some_and(|eles| eles.iter().any(|ele| !ele.is_diagonal_padding()))
+                })
+                .collect(),
+            directions,
+            depth: self.depth.clone(),
+            coverage: self.sharpness(SharpnessModel::Coverage),
+        }
     }
 
     pub fn to_shape(&self) -> EdgeShape {
@@ -228,6 +370,7 @@ impl Edge {
         EdgeShape {
             blank: [b1, b2],
             middle,
+            profile: self.to_profile(),
         }
     }
 }
@@ -254,6 +397,7 @@ pub enum ShapeTrend {
 pub struct EdgeShape {
     pub blank: [ShapeTrend; 2],
     pub middle: ShapeState,
+    pub profile: EdgeProfile,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -321,6 +465,16 @@ pub enum ViewElement {
 }
 
 impl ViewElement {
+    // Directions by their numpad symbol, `|` and `-` for lines passing through, none for paddings.
+    pub fn symbol(&self) -> Option<char> {
+        match self {
+            ViewElement::D(d) => Some(d.symbol()),
+            ViewElement::Vertical => Some('|'),
+            ViewElement::Horizontal => Some('-'),
+            _ => None,
+        }
+    }
+
     pub fn is_diagonal_padding(&self) -> bool {
         match self {
             ViewElement::DiagonalPad
@@ -365,7 +519,7 @@ impl StrucView {
                 let mut iter = path
                     .kpoints
                     .iter()
-                    .filter_map(|kp| if kp.is_mark() { None } else { Some(kp.pos) })
+                    .filter_map(|kp| if kp.is_on_curve() { Some(kp.pos) } else { None })
                     .map(|p| IndexPoint::new(values.h[&p.x], values.v[&p.y]));
 
                 {
@@ -487,19 +641,44 @@ impl StrucView {
                 Side::Back => Box::new(self.data.last().unwrap().iter().cloned()),
             },
         };
+        let inward = self.allocs.hv_get(axis);
+        let depth = |line: usize| {
+            let cell = |k: usize| match axis {
+                Axis::Horizontal => &self.data[line][k],
+                Axis::Vertical => &self.data[k][line],
+            };
+            let is_stroke = |k: &usize| cell(*k).iter().any(|ele| !ele.is_diagonal_padding());
+            match side {
+                Side::Front => (0..=inward.len())
+                    .find(is_stroke)
+                    .map(|k| inward[..k].iter().sum()),
+                Side::Back => (0..=inward.len())
+                    .rev()
+                    .find(is_stroke)
+                    .map(|k| inward[k..].iter().sum()),
+            }
+        };
+
         let mut edge_data = Vec::with_capacity(self.space_size().hv_get(axis) + 1);
-        for (eles, alloc) in data.zip(
-            self.allocs
-                .hv_get(axis.inverse())
-                .iter()
-                .chain(std::iter::once(&1)),
-        ) {
+        let mut edge_depth = Vec::with_capacity(edge_data.capacity());
+        for (line, (eles, alloc)) in data
+            .zip(
+                self.allocs
+                    .hv_get(axis.inverse())
+                    .iter()
+                    .chain(std::iter::once(&1)),
+            )
+            .enumerate()
+        {
             edge_data.push(Some(eles));
             edge_data.extend(vec![None; alloc - 1]);
+            edge_depth.push(depth(line));
+            edge_depth.extend(vec![None; alloc - 1]);
         }
         Edge {
             axis,
             data: edge_data,
+            depth: edge_depth,
         }
     }
 
@@ -589,6 +768,7 @@ mod tests {
                 Some(vec![ViewElement::D(Direction::Below)]),
                 Some(vec![ViewElement::D(Direction::Above)]),
             ],
+            depth: vec![Some(0); 2],
         };
 
         let mut edge = edge1.clone();
@@ -3,17 +3,19 @@ use serde_json as sj;
 
 use std::collections::BTreeMap;
 
+use super::settings::{Checker, sub_path};
 use crate::{
     base::*,
-    combination::view::{EdgeShape, ShapeState, ShapeTrend},
+    combination::view::{EdgeProfile, EdgeShape, ShapeState, ShapeTrend},
 };
 
 #[derive(Clone, Default)]
 pub struct EdgeCheck<T> {
     pub conditions: BTreeMap<String, sj::Value>,
     pub setup: [Option<T>; 2],
+    // Checks with higher priority are tried first.
+    pub priority: i32,
 
-    #[allow(dead_code)]
     note: String,
 
     data: BTreeMap<String, sj::Value>,
@@ -36,6 +38,33 @@ impl std::fmt::Display for CheckError {
 }
 
 impl<T> EdgeCheck<T> {
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    // Reports unknown keys and invalid values, `extra_conditions` take a boolean or null.
+    pub fn check(
+        &self,
+        path: &str,
+        checker: &mut Checker,
+        extra_keys: &[&str],
+        extra_conditions: &[&str],
+    ) {
+        self.data
+            .keys()
+            .filter(|k| {
+                !["conditions", "setup", "priority", "note"].contains(&k.as_str())
+                    && !extra_keys.contains(&k.as_str())
+            })
+            .for_each(|k| checker.issue(sub_path(path, k), "Unknown key!"));
+        check_conditions(
+            &sub_path(path, "conditions"),
+            &self.conditions,
+            checker,
+            extra_conditions,
+        );
+    }
+
     pub fn get_value<Target>(&self, key: &str) -> Option<Result<Target, serde_json::Error>>
     where
         Target: serde::de::DeserializeOwned,
@@ -43,6 +72,9 @@ impl<T> EdgeCheck<T> {
         self.data.get(key).map(|val| sj::from_value(val.clone()))
     }
 
+    // Conditions are ANDed, `any`/`all` take a list of condition objects and `not` takes one.
+    // `weighted` is `{"min": 1.0, "terms": [{"weight": 0.6, "when": {...}}, ...]}`, matching when
+    // the weights of the matching terms add up to `min`.
     pub fn is_match<F1, F2>(
         &self,
         axis: Axis,
@@ -55,27 +87,186 @@ impl<T> EdgeCheck<T> {
         F1: FnMut(usize, Axis, Side) -> EdgeShape,
         F2: FnMut(&str, &sj::Value) -> Result<bool, CheckError>,
     {
-        for (k, v) in self.conditions.iter() {
-            let k = k.as_str();
-            let ok = match k {
-                "axis" => match sj::from_value::<Axis>(v.clone()) {
-                    Ok(t_axis) => axis == t_axis,
+        match_conditions(
+            &self.conditions,
+            axis,
+            i,
+            length,
+            &mut get_edge,
+            &mut supplement,
+        )
+    }
+}
+
+// Indices of `checks` from the highest priority, equal priorities keep the list order.
+pub fn priority_order<T>(checks: &[EdgeCheck<T>]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..checks.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(checks[i].priority));
+    order
+}
+
+fn check_conditions<'a, I>(path: &str, conditions: I, checker: &mut Checker, extra: &[&str])
+where
+    I: IntoIterator<Item = (&'a String, &'a sj::Value)>,
+{
+    for (k, v) in conditions {
+        let path = sub_path(path, k);
+        match k.as_str() {
+            "axis" => {
+                checker.parse::<Axis>(&path, v);
+            }
+            "section" => {
+                checker.parse::<Section>(&path, v);
+            }
+            "edge1" | "edge2" | "edge1_cross" | "edge2_cross" | "front_edge" | "front_edge_f"
+            | "front_edge_b" | "back_edge" | "back_edge_f" | "back_edge_b" => {
+                checker.parse::<EdgeMatch>(&path, v);
+            }
+            "any" | "all" => match sub_conditions(k, v) {
+                Ok(list) => list.into_iter().enumerate().for_each(|(i, sub)| {
+                    check_conditions(&sub_path(&path, i), sub, checker, extra)
+                }),
+                Err(e) => checker.issue(path, e),
+            },
+            "not" => match v.as_object() {
+                Some(sub) => check_conditions(&path, sub, checker, extra),
+                None => checker.issue(path, format!("Expected an object, found `{v}`!")),
+            },
+            "weighted" => match weighted_terms(k, v) {
+                Ok((_, terms)) => terms.into_iter().enumerate().for_each(|(i, (_, sub))| {
+                    let path = sub_path(&sub_path(&sub_path(&path, "terms"), i), "when");
+                    check_conditions(&path, sub, checker, extra)
+                }),
+                Err(e) => checker.issue(path, e),
+            },
+            key if extra.contains(&key) => {
+                if !v.is_boolean() && !v.is_null() {
+                    checker.issue(path, format!("Expected a boolean, found `{v}`!"));
+                }
+            }
+            _ => checker.issue(path, "Unknown condition!"),
+        }
+    }
+}
+
+fn sub_conditions<'a>(
+    key: &str,
+    value: &'a sj::Value,
+) -> Result<Vec<&'a sj::value::Map<String, sj::Value>>, CheckError> {
+    value
+        .as_array()
+        .and_then(|list| list.iter().map(|sub| sub.as_object()).collect())
+        .ok_or_else(|| CheckError::ValueError {
+            key: key.to_string(),
+            value: format!("`{value}` is not a list of objects"),
+        })
+}
+
+type Terms<'a> = Vec<(f64, &'a sj::value::Map<String, sj::Value>)>;
+
+// The minimum score and the weighted conditions.
+fn weighted_terms<'a>(key: &str, value: &'a sj::Value) -> Result<(f64, Terms<'a>), CheckError> {
+    let error = |msg: &str| CheckError::ValueError {
+        key: key.to_string(),
+        value: format!("`{value}` {msg}"),
+    };
+    let obj = value.as_object().ok_or_else(|| error("is not an object"))?;
+    if let Some(k) = obj.keys().find(|k| !["min", "terms"].contains(&k.as_str())) {
+        return Err(error(&format!("has the unknown key `{k}`")));
+    }
+    let min = obj
+        .get("min")
+        .and_then(|v| v.as_f64())
+        .ok_or_else(|| error("requires a number `min`"))?;
+    let terms = obj
+        .get("terms")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| error("requires a list `terms`"))?
+        .iter()
+        .map(|term| {
+            let term = term.as_object()?;
+            match (term.get("weight")?.as_f64(), term.get("when")?.as_object()) {
+                (Some(weight), Some(when)) if term.len() == 2 => Some((weight, when)),
+                _ => None,
+            }
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| error("has a term other than `{\"weight\": number, \"when\": object}`"))?;
+    Ok((min, terms))
+}
+
+fn match_conditions<'a, I, F1, F2>(
+    conditions: I,
+    axis: Axis,
+    i: usize,
+    length: usize,
+    get_edge: &mut F1,
+    supplement: &mut F2,
+) -> Result<bool, CheckError>
+where
+    I: IntoIterator<Item = (&'a String, &'a sj::Value)>,
+    F1: FnMut(usize, Axis, Side) -> EdgeShape,
+    F2: FnMut(&str, &sj::Value) -> Result<bool, CheckError>,
+{
+    for (k, v) in conditions {
+        let k = k.as_str();
+        let ok = match k {
+            "axis" => match sj::from_value::<Axis>(v.clone()) {
+                Ok(t_axis) => axis == t_axis,
+                Err(e) => {
+                    return Err(CheckError::ValueError {
+                        key: k.to_string(),
+                        value: e.to_string(),
+                    });
+                }
+            },
+            "section" => match sj::from_value::<Section>(v.clone()) {
+                Ok(section) => {
+                    if i == 0 {
+                        section == Section::Start
+                    } else if i + 1 == length {
+                        section == Section::End
+                    } else {
+                        section == Section::Middle
+                    }
+                }
+                Err(e) => {
+                    return Err(CheckError::ValueError {
+                        key: k.to_string(),
+                        value: e.to_string(),
+                    });
+                }
+            },
+            "edge1" | "edge2" | "edge1_cross" | "edge2_cross" => {
+                match sj::from_value::<EdgeMatch>(v.clone()) {
+                    Ok(rule) => {
+                        let (axis, side) = match k {
+                            "edge1" => (axis, Side::Front),
+                            "edge2" => (axis, Side::Back),
+                            "edge1_cross" => (axis.inverse(), Side::Front),
+                            "edge2_cross" => (axis.inverse(), Side::Back),
+                            _ => unreachable!(),
+                        };
+                        rule.is_match(&get_edge(i, axis, side))
+                    }
                     Err(e) => {
                         return Err(CheckError::ValueError {
                             key: k.to_string(),
                             value: e.to_string(),
                         });
                     }
-                },
-                "section" => match sj::from_value::<Section>(v.clone()) {
-                    Ok(section) => {
-                        if i == 0 {
-                            section == Section::Start
-                        } else if i + 1 == length {
-                            section == Section::End
-                        } else {
-                            section == Section::Middle
-                        }
+                }
+            }
+            "front_edge" | "front_edge_f" | "front_edge_b" => {
+                match sj::from_value::<EdgeMatch>(v.clone()) {
+                    Ok(rule) => {
+                        let (axis, side) = match k {
+                            "front_edge" => (axis.inverse(), Side::Back),
+                            "front_edge_f" => (axis, Side::Front),
+                            "front_edge_b" => (axis, Side::Back),
+                            _ => unreachable!(),
+                        };
+                        i != 0 && rule.is_match(&get_edge(i - 1, axis, side))
                     }
                     Err(e) => {
                         return Err(CheckError::ValueError {
@@ -83,73 +274,63 @@ impl<T> EdgeCheck<T> {
                             value: e.to_string(),
                         });
                     }
-                },
-                "edge1" | "edge2" | "edge1_cross" | "edge2_cross" => {
-                    match sj::from_value::<EdgeMatch>(v.clone()) {
-                        Ok(rule) => {
-                            let (axis, side) = match k {
-                                "edge1" => (axis, Side::Front),
-                                "edge2" => (axis, Side::Back),
-                                "edge1_cross" => (axis.inverse(), Side::Front),
-                                "edge2_cross" => (axis.inverse(), Side::Back),
-                                _ => unreachable!(),
-                            };
-                            rule.is_match(&get_edge(i, axis, side))
-                        }
-                        Err(e) => {
-                            return Err(CheckError::ValueError {
-                                key: k.to_string(),
-                                value: e.to_string(),
-                            });
-                        }
+                }
+            }
+            "back_edge" | "back_edge_f" | "back_edge_b" => {
+                match sj::from_value::<EdgeMatch>(v.clone()) {
+                    Ok(rule) => {
+                        let (axis, side) = match k {
+                            "back_edge" => (axis.inverse(), Side::Front),
+                            "back_edge_f" => (axis, Side::Front),
+                            "back_edge_b" => (axis, Side::Back),
+                            _ => unreachable!(),
+                        };
+                        i + 1 != length && rule.is_match(&get_edge(i + 1, axis, side))
+                    }
+                    Err(e) => {
+                        return Err(CheckError::ValueError {
+                            key: k.to_string(),
+                            value: e.to_string(),
+                        });
                     }
                 }
-                "front_edge" | "front_edge_f" | "front_edge_b" => {
-                    match sj::from_value::<EdgeMatch>(v.clone()) {
-                        Ok(rule) => {
-                            let (axis, side) = match k {
-                                "front_edge" => (axis.inverse(), Side::Back),
-                                "front_edge_f" => (axis, Side::Front),
-                                "front_edge_b" => (axis, Side::Back),
-                                _ => unreachable!(),
-                            };
-                            i != 0 && rule.is_match(&get_edge(i - 1, axis, side))
-                        }
-                        Err(e) => {
-                            return Err(CheckError::ValueError {
-                                key: k.to_string(),
-                                value: e.to_string(),
-                            });
-                        }
+            }
+            "any" | "all" => {
+                let mut r = k == "all";
+                for sub in sub_conditions(k, v)? {
+                    if match_conditions(sub, axis, i, length, get_edge, supplement)? != r {
+                        r = !r;
+                        break;
                     }
                 }
-                "back_edge" | "back_edge_f" | "back_edge_b" => {
-                    match sj::from_value::<EdgeMatch>(v.clone()) {
-                        Ok(rule) => {
-                            let (axis, side) = match k {
-                                "back_edge" => (axis.inverse(), Side::Front),
-                                "back_edge_f" => (axis, Side::Front),
-                                "back_edge_b" => (axis, Side::Back),
-                                _ => unreachable!(),
-                            };
-                            i + 1 != length && rule.is_match(&get_edge(i + 1, axis, side))
-                        }
-                        Err(e) => {
-                            return Err(CheckError::ValueError {
-                                key: k.to_string(),
-                                value: e.to_string(),
-                            });
-                        }
+                r
+            }
+            "not" => match v.as_object() {
+                Some(sub) => !match_conditions(sub, axis, i, length, get_edge, supplement)?,
+                None => {
+                    return Err(CheckError::ValueError {
+                        key: k.to_string(),
+                        value: format!("`{v}` is not an object"),
+                    });
+                }
+            },
+            "weighted" => {
+                let (min, terms) = weighted_terms(k, v)?;
+                let mut score = 0.0;
+                for (weight, sub) in terms {
+                    if match_conditions(sub, axis, i, length, get_edge, supplement)? {
+                        score += weight;
                     }
                 }
-                _ => supplement(k, v)?,
-            };
-            if ok == false {
-                return Ok(false);
+                score >= min
             }
+            _ => supplement(k, v)?,
+        };
+        if !ok {
+            return Ok(false);
         }
-        Ok(true)
     }
+    Ok(true)
 }
 
 impl<'de, T> Deserialize<'de> for EdgeCheck<T>
@@ -163,7 +344,10 @@ where
         let data: BTreeMap<String, sj::Value> = Deserialize::deserialize(deserializer)?;
         let note = data
             .get("note")
-            .map(|val| val.to_string())
+            .map(|val| match val.as_str() {
+                Some(note) => note.to_string(),
+                None => val.to_string(),
+            })
             .unwrap_or_default();
 
         let key = "conditions";
@@ -180,20 +364,73 @@ where
             .and_then(|val| Deserialize::deserialize(val.clone()))
             .map_err(|e| serde::de::Error::custom(e))?;
 
+        let priority = match data.get("priority") {
+            Some(val) => sj::from_value(val.clone()).map_err(serde::de::Error::custom)?,
+            None => 0,
+        };
+
         Ok(Self {
             conditions,
             setup,
+            priority,
             note,
             data,
         })
     }
 }
 
+// `[min, max]`, null for no limit.
+pub type Range<T> = [Option<T>; 2];
+
+fn in_range<T: PartialOrd>(range: &Range<T>, val: T) -> bool {
+    range[0].as_ref().is_none_or(|min| val >= *min)
+        && range[1].as_ref().is_none_or(|max| val <= *max)
+}
+
+// Conditions on `EdgeProfile`, given beside `shape` in the object syntax of `EdgeMatch`.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileMatch {
+    // Number of units touched by strokes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub occupied: Option<Range<usize>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<Range<f32>>,
+    // The deepest recess, an edge without strokes only matches `[null, null]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth: Option<Range<usize>>,
+    // Counts of elements by their symbol, like `{"6": [2, null]}`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dirs: BTreeMap<char, Range<usize>>,
+}
+
+impl ProfileMatch {
+    pub fn is_match(&self, profile: &EdgeProfile) -> bool {
+        self.occupied
+            .is_none_or(|range| in_range(&range, profile.occupied()))
+            && self
+                .coverage
+                .is_none_or(|range| in_range(&range, profile.coverage))
+            && self.depth.is_none_or(|range| match profile.max_depth() {
+                Some(depth) => in_range(&range, depth),
+                None => range == [None, None],
+            })
+            && self.dirs.iter().all(|(c, range)| {
+                in_range(
+                    range,
+                    profile.directions.get(c).copied().unwrap_or_default(),
+                )
+            })
+    }
+}
+
+// `["o", "]", "x"]`, or `{"shape": ["o", "]", "x"], "occupied": [2, null], ...}` to match the `ProfileMatch` too.
 #[derive(Clone)]
 pub struct EdgeMatch {
     blank: [Option<Vec<ShapeTrend>>; 2],
     middle: Option<Vec<ShapeState>>,
     not: bool,
+    profile: Option<ProfileMatch>,
 }
 
 impl EdgeMatch {
@@ -213,7 +450,11 @@ impl EdgeMatch {
             .middle
             .as_ref()
             .map(|m| m.iter().find(|s| **s == shape.middle).is_some())
-            .unwrap_or(true);
+            .unwrap_or(true)
+            && self
+                .profile
+                .as_ref()
+                .is_none_or(|p| p.is_match(&shape.profile));
         return r != self.not;
     }
 }
@@ -271,14 +512,32 @@ impl Serialize for EdgeMatch {
             }
         }
 
-        let mut seq = serializer.serialize_seq(None)?;
-        seq.serialize_element(&to_blank_str(&self.blank[0]))?;
-        seq.serialize_element(&to_middle_str(&self.middle))?;
-        seq.serialize_element(&to_blank_str(&self.blank[1]))?;
-        if self.not {
-            seq.serialize_element("!")?;
+        struct Shape<'a>(&'a EdgeMatch);
+
+        impl Serialize for Shape<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                let mut seq = serializer.serialize_seq(None)?;
+                seq.serialize_element(&to_blank_str(&self.0.blank[0]))?;
+                seq.serialize_element(&to_middle_str(&self.0.middle))?;
+                seq.serialize_element(&to_blank_str(&self.0.blank[1]))?;
+                if self.0.not {
+                    seq.serialize_element("!")?;
+                }
+                seq.end()
+            }
+        }
+
+        match &self.profile {
+            None => Shape(self).serialize(serializer),
+            Some(profile) => {
+                let mut data = sj::to_value(profile).map_err(serde::ser::Error::custom)?;
+                data["shape"] = sj::to_value(Shape(self)).map_err(serde::ser::Error::custom)?;
+                data.serialize(serializer)
+            }
         }
-        seq.end()
     }
 }
 
@@ -333,30 +592,51 @@ impl<'de> Deserialize<'de> for EdgeMatch {
             }
         };
 
+        let from_array = |array: Vec<sj::Value>| {
+            if array.len() != 3 && array.len() != 4 {
+                Err(serde::de::Error::custom(format!(
+                    "Standard edge element is not {}",
+                    array.len()
+                )))
+            } else if !array.iter().all(|ele| ele.is_string()) {
+                Err(serde::de::Error::custom(format!(
+                    "Failed convert to IntervalRule in {:?}",
+                    array
+                )))
+            } else {
+                let list: Vec<_> = array.iter().map(|ele| ele.as_str().unwrap()).collect();
+                let blank = [from_blank_str(list[0])?, from_blank_str(list[2])?];
+                let middle = from_middle_str(list[1])?;
+                let not = list
+                    .get(3)
+                    .filter(|str| **str == "!")
+                    .map(|_| true)
+                    .unwrap_or(false);
+
+                Ok(Self {
+                    blank,
+                    middle,
+                    not,
+                    profile: None,
+                })
+            }
+        };
+
         match Deserialize::deserialize(deserializer)? {
-            serde_json::Value::Array(array) => {
-                if array.len() != 3 && array.len() != 4 {
-                    Err(serde::de::Error::custom(format!(
-                        "Standard edge element is not {}",
-                        array.len()
-                    )))
-                } else if !array.iter().all(|ele| ele.is_string()) {
-                    Err(serde::de::Error::custom(format!(
-                        "Failed convert to IntervalRule in {:?}",
-                        array
-                    )))
-                } else {
-                    let list: Vec<_> = array.iter().map(|ele| ele.as_str().unwrap()).collect();
-                    let blank = [from_blank_str(list[0])?, from_blank_str(list[2])?];
-                    let middle = from_middle_str(list[1])?;
-                    let not = list
-                        .get(3)
-                        .filter(|str| **str == "!")
-                        .map(|_| true)
-                        .unwrap_or(false);
-
-                    Ok(Self { blank, middle, not })
-                }
+            sj::Value::Array(array) => from_array(array),
+            sj::Value::Object(mut obj) => {
+                let mut rule = match obj.remove("shape") {
+                    Some(sj::Value::Array(array)) => from_array(array)?,
+                    Some(val) => {
+                        return Err(serde::de::Error::custom(format!(
+                            "Failed convert to edge shape in {val}"
+                        )));
+                    }
+                    None => from_array(vec!["*".into(); 3])?,
+                };
+                rule.profile =
+                    Some(sj::from_value(sj::Value::Object(obj)).map_err(serde::de::Error::custom)?);
+                Ok(rule)
             }
             val => Err(serde::de::Error::custom(format!(
                 "Failed convert to IntervalRule in {}",
@@ -385,6 +665,7 @@ mod tests {
                     instances.push(EdgeShape {
                         blank: [f, b],
                         middle: m,
+                        profile: Default::default(),
                     });
                 }
             }
@@ -429,4 +710,145 @@ mod tests {
             assert_eq!(rule.is_match(shape), b, "{i}");
         });
     }
+
+    #[test]
+    fn test_profile_match() {
+        use crate::combination::{StrucComb, StrucProto};
+
+        let left_edge = |paths: Vec<_>| {
+            StrucComb::new_single("name".to_string(), StrucProto::from(paths))
+                .get_edge(Axis::Horizontal, Side::Front, false)
+                .to_shape()
+        };
+        let square = || {
+            KeyPath::from([
+                key_pos(0, 0),
+                key_pos(2, 0),
+                key_pos(2, 2),
+                key_pos(0, 2),
+                key_pos(0, 0),
+            ])
+        };
+        let kou = left_edge(vec![square()]);
+        let ri = left_edge(vec![
+            square(),
+            KeyPath::from([key_pos(0, 1), key_pos(2, 1)]),
+        ]);
+        let hook = left_edge(vec![KeyPath::from([
+            key_pos(0, 0),
+            key_pos(2, 0),
+            key_pos(2, 2),
+        ])]);
+        assert_eq!(kou.profile.occupied(), 2);
+        assert_eq!(ri.profile.occupied(), 3);
+        assert_eq!(hook.profile.max_depth(), Some(2));
+
+        let rule: EdgeMatch = serde_json::from_value(json!(["*", "]", "*"])).unwrap();
+        assert!(rule.is_match(&kou) && rule.is_match(&ri));
+
+        let js = json!({ "shape": ["*", "]", "*"], "occupied": [3, null] });
+        let rule: EdgeMatch = serde_json::from_value(js.clone()).unwrap();
+        assert!(!rule.is_match(&kou) && rule.is_match(&ri));
+        assert_eq!(serde_json::to_value(&rule).unwrap(), js);
+
+        let rule: EdgeMatch =
+            serde_json::from_value(json!({ "dirs": { "6": [null, 2] }, "depth": [null, 0] }))
+                .unwrap();
+        assert!(rule.is_match(&kou) && !rule.is_match(&ri) && !rule.is_match(&hook));
+
+        let rule: EdgeMatch = serde_json::from_value(
+            json!({ "shape": ["*", "*", "*", "!"], "coverage": [1.0, null] }),
+        )
+        .unwrap();
+        assert!(!rule.is_match(&kou) && rule.is_match(&hook));
+
+        assert!(serde_json::from_value::<EdgeMatch>(json!({ "occupy": [1, 2] })).is_err());
+    }
+
+    #[test]
+    fn test_edge_check() {
+        let dense = EdgeShape {
+            blank: [ShapeTrend::None; 2],
+            middle: ShapeState::Dense,
+            profile: Default::default(),
+        };
+        let acute = EdgeShape {
+            blank: [ShapeTrend::None; 2],
+            middle: ShapeState::Acute,
+            profile: Default::default(),
+        };
+        let checks: Vec<EdgeCheck<f32>> = serde_json::from_value(json!([
+            {
+                "conditions": { "section": "Start", "edge2": ["*", "]", "*"] },
+                "setup": [1.0, null]
+            },
+            {
+                "conditions": {
+                    "any": [{ "edge1": ["*", "]", "*"] }, { "back_edge": ["*", ">", "*"] }],
+                    "not": { "section": "End" }
+                },
+                "setup": [null, 1.0],
+                "priority": 1
+            }
+        ]))
+        .unwrap();
+        assert_eq!(priority_order(&checks), vec![1, 0]);
+
+        let edges = [
+            [dense.clone(), acute.clone()],
+            [acute.clone(), dense.clone()],
+        ];
+        let is_match = |check: &EdgeCheck<f32>, i: usize| {
+            check
+                .is_match(
+                    Axis::Horizontal,
+                    i,
+                    2,
+                    |i, _, side| edges[i][side.n()].clone(),
+                    |k, _| Err(CheckError::UnknowKey(k.to_string())),
+                )
+                .ok()
+        };
+        assert_eq!(is_match(&checks[0], 0), Some(false));
+        assert_eq!(is_match(&checks[0], 1), Some(false));
+        assert_eq!(is_match(&checks[1], 0), Some(true));
+        assert_eq!(is_match(&checks[1], 1), Some(false));
+
+        let check: EdgeCheck<f32> = serde_json::from_value(json!({
+            "conditions": { "all": [{ "edge1": ["*", ">", "*"] }, { "unknown": 0 }] },
+            "setup": [null, null]
+        }))
+        .unwrap();
+        assert_eq!(is_match(&check, 0), Some(false));
+        assert_eq!(is_match(&check, 1), None);
+
+        let weighted = |min: f64| -> EdgeCheck<f32> {
+            serde_json::from_value(json!({
+                "conditions": {
+                    "weighted": {
+                        "min": min,
+                        "terms": [
+                            { "weight": 0.5, "when": { "edge1": ["*", "]", "*"] } },
+                            { "weight": 0.25, "when": { "section": "Start" } },
+                            { "weight": -0.5, "when": { "edge2": ["*", "]", "*"] } }
+                        ]
+                    }
+                },
+                "setup": [null, null]
+            }))
+            .unwrap()
+        };
+        // Scores are 0.75 for the first and -0.5 for the second.
+        assert_eq!(is_match(&weighted(0.75), 0), Some(true));
+        assert_eq!(is_match(&weighted(0.75), 1), Some(false));
+        assert_eq!(is_match(&weighted(-0.5), 1), Some(true));
+        assert_eq!(is_match(&weighted(0.0), 1), Some(false));
+
+        let check: EdgeCheck<f32> = serde_json::from_value(json!({
+            "conditions": { "weighted": { "min": 1, "terms": [{ "weight": 1 }] } },
+            "setup": [null, null]
+        }))
+        .unwrap();
+        assert_eq!(is_match(&check, 0), None);
+    }
 }
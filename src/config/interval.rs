@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
+use serde_json as sj;
 
-use super::EdgeMatch;
+use super::{
+    EdgeMatch,
+    settings::{Checker, sub_path},
+};
 use crate::{base::Axis, combination::view::EdgeShape};
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -14,6 +18,35 @@ pub struct IntervalMatch {
 }
 
 impl IntervalMatch {
+    // Parses a rule of `interval.rules`, reporting the failed fields.
+    pub fn check(path: &str, value: &sj::Value, checker: &mut Checker) -> Option<Self> {
+        checker.unknown_keys(
+            path,
+            value,
+            &["inverse", "axis", "val", "note", "rule1", "rule2"],
+        );
+        let count = checker.issues.len();
+        if let Some(val) = value.get("inverse") {
+            checker.parse::<bool>(&sub_path(path, "inverse"), val);
+        }
+        if let Some(val) = value.get("axis") {
+            checker.parse::<Option<Axis>>(&sub_path(path, "axis"), val);
+        }
+        if let Some(val) = value.get("val") {
+            checker.parse::<usize>(&sub_path(path, "val"), val);
+        }
+        for key in ["rule1", "rule2"] {
+            if let Some(val) = value.get(key) {
+                checker.parse::<EdgeMatch>(&sub_path(path, key), val);
+            }
+        }
+
+        match checker.issues.len() == count {
+            true => checker.parse(path, value),
+            false => None,
+        }
+    }
+
     pub fn is_match(&self, edge1: &EdgeShape, edge2: &EdgeShape, axis: Axis) -> Option<usize> {
         let mut r = None;
         if self.axis.unwrap_or(axis) == axis {
@@ -1,21 +1,32 @@
 pub mod interval;
 use interval::IntervalMatch;
 pub mod edge_check;
-pub use edge_check::{CheckError, EdgeCheck, EdgeMatch};
+pub use edge_check::{CheckError, EdgeCheck, EdgeMatch, priority_order};
+pub mod settings;
+use settings::{Checker, sub_path};
+pub use settings::{
+    ConfigIssue, IntervalSetting, SmoothSetting, SpaceAssign, SpaceCtrls, SpaceMode, Strictness,
+};
 
 use crate::{
     base::*,
     combination::{StrucComb, attrs, view},
-    construct::{CpAttrs, CstType},
+    construct::{CharSet, CharSetError, CharSets, CpAttrs, CstType},
+    service::trace::{IntervalSource, TraceEvent, Tracer},
 };
 
 use serde::{Deserialize, Serialize};
 use serde_json as sj;
 use sj::json;
 
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::atomic::{self, AtomicU64},
+};
 
 const DEFAULT_MIN_VALUE: f32 = 0.05;
+static REVISION: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Clone, Default)]
 pub struct ZiMian(Vec<(usize, f32)>);
@@ -90,8 +101,45 @@ mod keys {
     pub const SPACE_ASSIGN: &str = "space_assign";
     pub const INTERVAL: &str = "interval";
     pub const MAIN_EDGE: &str = "main_edge";
+    pub const CHARSETS: &str = "charsets";
+    pub const STRICTNESS: &str = "strictness";
+    pub const SHARPNESS: &str = "sharpness";
+    pub const SMOOTH: &str = "smooth";
+
+    pub const ALL: [&str; 18] = [
+        SIZE,
+        UNITS,
+        ZIMIAN,
+        SUPPLEMENT,
+        TYPE_REPLACE,
+        PLACE_REPLACE,
+        REDUCE_TRIGGER,
+        REPLACE_TRIGGER,
+        VISUAL_CORR,
+        REDUCE_REPLACE,
+        SPACE_CTRLS,
+        SPACE_ASSIGN,
+        INTERVAL,
+        MAIN_EDGE,
+        CHARSETS,
+        STRICTNESS,
+        SHARPNESS,
+        SMOOTH,
+    ];
 }
 
+// Conditions of `main_edge` besides those of `EdgeCheck`.
+const MAIN_EDGE_CONDITIONS: [&str; 8] = [
+    "not_set",
+    "state_f",
+    "state_b",
+    "state_front_f",
+    "state_front_b",
+    "state_back_f",
+    "state_back_b",
+    "is_max",
+];
+
 #[derive(Clone)]
 pub struct Config {
     pub size: DataHV<f32>,
@@ -103,7 +151,22 @@ pub struct Config {
     type_replace: BTreeMap<char, BTreeMap<Section, BTreeMap<String, String>>>,
     place_replace: BTreeMap<String, Vec<(String, String)>>,
 
+    reduce_trigger: DataHV<f32>,
+    replace_trigger: DataHV<f32>,
+    visual_corr: DataHV<f32>,
+    sharpness: DataHV<view::SharpnessModel>,
+    reduce_replace: DataHV<BTreeMap<String, String>>,
+    space_ctrls: SpaceCtrls,
+    space_assign: SpaceAssign,
+    interval: IntervalSetting,
+    main_edge: Vec<EdgeCheck<bool>>,
+    smooth: Option<SmoothSetting>,
+
+    strictness: Strictness,
+    issues: Vec<ConfigIssue>,
     data: sj::Value,
+    // Distinct for every parsed config, shared by its clones.
+    revision: u64,
 }
 
 impl Serialize for Config {
@@ -122,86 +185,308 @@ impl<'de> Deserialize<'de> for Config {
     {
         let data: sj::Value = Deserialize::deserialize(deserializer)?;
 
+        let mut checker = Checker::default();
+        checker.unknown_keys("", &data, &keys::ALL);
+
         let key = keys::SIZE;
-        let parse = |val: &sj::Value| match val.as_f64() {
-            Some(size) => Ok::<f64, D::Error>(size),
-            None => Err(serde::de::Error::custom(format!(
-                "Config Error: Invalid `{val}` in `{key}`!"
-            ))),
-        };
         let size = match data.get(key) {
             None => DataHV::splat(1.0),
-            Some(val) => match val {
-                sj::Value::Object(obj) => DataHV::new(
-                    parse(obj.get("h").unwrap_or(&json!(1)))?,
-                    parse(obj.get("v").unwrap_or(&json!(1)))?,
-                ),
-                val => DataHV::splat(parse(val)?),
-            },
-        }
-        .into_map(|v| v as f32);
+            Some(val) => checker
+                .axis_number(&sub_path("", key), val)
+                .into_map(|v| v.unwrap_or(1.0)),
+        };
 
         let key = keys::UNITS;
-        let parse = |val: &sj::Value| {
-            sj::from_value::<Vec<f32>>(val.clone()).map_err(|e| {
-                serde::de::Error::custom(format!("Config Error: Invalid `{val}` in `{key}`!\n{e}"))
-            })
-        };
         let units: DataHV<Vec<f32>> = match data.get(key) {
             None => DataHV::splat(vec![DEFAULT_MIN_VALUE]),
-            Some(val) => match val {
-                sj::Value::Object(obj) => DataHV::new(
-                    parse(obj.get("h").unwrap_or(&json!([DEFAULT_MIN_VALUE])))?,
-                    parse(obj.get("v").unwrap_or(&json!([DEFAULT_MIN_VALUE])))?,
-                ),
-                val => DataHV::splat(parse(val)?),
-            },
+            Some(val) => checker
+                .axis_parse(&sub_path("", key), val)
+                .into_map(|v| v.unwrap_or_else(|| vec![DEFAULT_MIN_VALUE])),
         };
 
         let key = keys::ZIMIAN;
-        let parse = |val: &sj::Value| {
-            sj::from_value::<Vec<(usize, f32)>>(val.clone())
-                .map(|setting| ZiMian(setting))
-                .map_err(|e| {
-                    serde::de::Error::custom(format!(
-                        "Config Error: Invalid `{val}` in `{key}`!\n{e}"
-                    ))
-                })
-        };
         let zimian: DataHV<ZiMian> = match data.get(key) {
             None => DataHV::splat(ZiMian(vec![(0, 1.0)])),
-            Some(val) => match val {
-                sj::Value::Object(obj) if obj.contains_key("h") && obj.contains_key("v") => {
-                    DataHV::new(parse(obj.get("h").unwrap())?, parse(obj.get("v").unwrap())?)
-                }
-                val => DataHV::splat(parse(val)?),
-            },
+            Some(val) => checker
+                .axis_parse(&sub_path("", key), val)
+                .into_map(|v| ZiMian(v.unwrap_or_else(|| vec![(0, 1.0)]))),
         };
 
         let key = keys::SUPPLEMENT;
-        let supplement = match data.get(key).map(|val| sj::from_value(val.clone())) {
-            None => Default::default(),
-            Some(r) => {
-                r.map_err(|e| serde::de::Error::custom(format!("Config Error: `{key}` {e}")))?
-            }
-        };
+        let supplement = data
+            .get(key)
+            .and_then(|val| checker.parse(&sub_path("", key), val))
+            .unwrap_or_default();
 
         let key = keys::TYPE_REPLACE;
-        let type_replace = match data.get(key).map(|val| sj::from_value(val.clone())) {
-            None => Default::default(),
-            Some(r) => {
-                r.map_err(|e| serde::de::Error::custom(format!("Config Error: `{key}` {e}")))?
-            }
-        };
+        let type_replace = data
+            .get(key)
+            .and_then(|val| checker.parse(&sub_path("", key), val))
+            .unwrap_or_default();
 
         let key = keys::PLACE_REPLACE;
-        let place_replace = match data.get(key).map(|val| sj::from_value(val.clone())) {
-            None => Default::default(),
-            Some(r) => {
-                r.map_err(|e| serde::de::Error::custom(format!("Config Error: `{key}` {e}")))?
+        let place_replace = data
+            .get(key)
+            .and_then(|val| checker.parse(&sub_path("", key), val))
+            .unwrap_or_default();
+
+        let mut axis_number = |key: &str| {
+            data.get(key)
+                .map(|val| {
+                    checker
+                        .axis_number(&sub_path("", key), val)
+                        .into_map(|v| v.unwrap_or(0.0))
+                })
+                .unwrap_or_default()
+        };
+        let reduce_trigger = axis_number(keys::REDUCE_TRIGGER);
+        let replace_trigger = axis_number(keys::REPLACE_TRIGGER);
+        let visual_corr = axis_number(keys::VISUAL_CORR);
+
+        // `{"model": ...}` or `{"h": {"model": ...}, "v": ...}`
+        let key = keys::SHARPNESS;
+        let mut sharpness = DataHV::<view::SharpnessModel>::default();
+        if let Some(val) = data.get(key) {
+            let path = sub_path("", key);
+            if val.get("model").is_some() {
+                sharpness = DataHV::splat(checker.parse(&path, val).unwrap_or_default());
+            } else {
+                checker.unknown_keys(&path, val, &["h", "v"]);
+                for axis in Axis::list() {
+                    if let Some(val) = val.get(axis.symbol()) {
+                        *sharpness.hv_get_mut(axis) = checker
+                            .parse(&sub_path(&path, axis.symbol()), val)
+                            .unwrap_or_default();
+                    }
+                }
+            }
+        }
+
+        let key = keys::REDUCE_REPLACE;
+        let reduce_replace = match data.get(key) {
+            Some(val) => {
+                let path = sub_path("", key);
+                checker.unknown_keys(&path, val, &["h", "v"]);
+                Axis::hv().into_map(|axis| {
+                    val.get(axis.symbol())
+                        .and_then(|val| checker.parse(&sub_path(&path, axis.symbol()), val))
+                        .unwrap_or_default()
+                })
             }
+            None => Default::default(),
         };
 
+        let key = keys::SPACE_CTRLS;
+        let mut space_ctrls = SpaceCtrls::default();
+        if let Some(val) = data.get(key) {
+            let path = sub_path("", key);
+            match val.as_object() {
+                Some(obj) => {
+                    if let Some(order) = obj.get("order") {
+                        space_ctrls.order = checker
+                            .parse::<Vec<String>>(&sub_path(&path, "order"), order)
+                            .unwrap_or_default();
+                    }
+                    for (i, ctrl) in space_ctrls.order.iter().enumerate() {
+                        if !obj.contains_key(ctrl) {
+                            checker.issue(
+                                sub_path(&sub_path(&path, "order"), i),
+                                format!("Missing settings of space control `{ctrl}`!"),
+                            );
+                        }
+                    }
+                    obj.keys()
+                        .filter(|k| *k != "order" && !space_ctrls.order.contains(k))
+                        .for_each(|k| {
+                            checker.issue(sub_path(&path, k), "Space control is not in `order`!")
+                        });
+                    if let Some(trend) = obj.get("trend") {
+                        let path = sub_path(&path, "trend");
+                        checker.unknown_keys(&path, trend, &["scale"]);
+                        if let Some(scale) = trend.get("scale") {
+                            let path = sub_path(&path, "scale");
+                            checker.unknown_keys(&path, scale, &["h", "v"]);
+                            for axis in Axis::list() {
+                                if let Some(val) = scale.get(axis.symbol()) {
+                                    let path = sub_path(&path, axis.symbol());
+                                    if let Some(list) = checker.parse::<Vec<sj::Value>>(&path, val)
+                                    {
+                                        for (i, rule) in list.iter().enumerate() {
+                                            let path = sub_path(&path, i);
+                                            if let Some(rule) =
+                                                checker.parse::<EdgeCheck<f32>>(&path, rule)
+                                            {
+                                                rule.check(&path, &mut checker, &[], &[]);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    if let Some(gray) = obj.get("gray") {
+                        let path = sub_path(&path, "gray");
+                        checker.unknown_keys(&path, gray, &["stroke", "factor"]);
+                        for key in ["stroke", "factor"] {
+                            if let Some(val) = gray.get(key) {
+                                checker.axis_number(&sub_path(&path, key), val);
+                            }
+                        }
+                    }
+                    if let Some(align) = obj.get("align") {
+                        let path = sub_path(&path, "align");
+                        checker.unknown_keys(&path, align, &["tolerance"]);
+                        if let Some(val) = align.get("tolerance") {
+                            checker.axis_number(&sub_path(&path, "tolerance"), val);
+                        }
+                    }
+                    space_ctrls.settings = Some(obj.clone());
+                }
+                None => checker.issue(path, format!("Expected an object, found `{val}`!")),
+            }
+        }
+
+        let key = keys::SPACE_ASSIGN;
+        let mut space_assign = SpaceAssign::default();
+        if let Some(val) = data.get(key) {
+            let path = sub_path("", key);
+            checker.unknown_keys(
+                &path,
+                val,
+                &["white", "visual_corr", "unit", "mode", "symmetry"],
+            );
+            if let Some(val) = val.get("white") {
+                space_assign.white = checker
+                    .axis_side_number(&sub_path(&path, "white"), val)
+                    .into_map(|v| v.map(|v| v.unwrap_or(1.0)));
+            }
+            if let Some(val) = val.get("visual_corr") {
+                space_assign.visual_corr = checker
+                    .axis_side_number(&sub_path(&path, "visual_corr"), val)
+                    .into_map(|v| v.map(|v| v.unwrap_or(0.0)));
+            }
+            if let Some(val) = val.get("unit") {
+                space_assign.unit = checker.axis_number(&sub_path(&path, "unit"), val);
+            }
+            if let Some(val) = val.get("mode") {
+                space_assign.mode = checker
+                    .parse(&sub_path(&path, "mode"), val)
+                    .unwrap_or_default();
+            }
+            if let Some(val) = val.get("symmetry") {
+                space_assign.symmetry = checker
+                    .parse(&sub_path(&path, "symmetry"), val)
+                    .unwrap_or_default();
+            }
+        }
+
+        let key = keys::INTERVAL;
+        let mut interval = IntervalSetting::default();
+        if let Some(val) = data.get(key) {
+            let path = sub_path("", key);
+            checker.unknown_keys(&path, val, &["limit", "rules"]);
+            if let Some(val) = val.get("limit") {
+                interval.limit = checker.axis_number(&sub_path(&path, "limit"), val);
+            }
+            if let Some(val) = val.get("rules") {
+                let path = sub_path(&path, "rules");
+                match val.as_array() {
+                    Some(list) => {
+                        interval.rules = list
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(i, rule)| {
+                                IntervalMatch::check(&sub_path(&path, i), rule, &mut checker)
+                            })
+                            .collect()
+                    }
+                    None => checker.issue(path, format!("Expected an array, found `{val}`!")),
+                }
+            }
+        }
+
+        let key = keys::MAIN_EDGE;
+        let mut main_edge = vec![];
+        if let Some(val) = data.get(key) {
+            let path = sub_path("", key);
+            match val.as_array() {
+                Some(list) => {
+                    main_edge = list
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, rule)| {
+                            let path = sub_path(&path, i);
+                            checker
+                                .parse::<EdgeCheck<bool>>(&path, rule)
+                                .inspect(|rule| {
+                                    rule.check(
+                                        &path,
+                                        &mut checker,
+                                        &["force"],
+                                        &MAIN_EDGE_CONDITIONS,
+                                    )
+                                })
+                        })
+                        .collect()
+                }
+                None => checker.issue(path, format!("Expected an array, found `{val}`!")),
+            }
+        }
+
+        let key = keys::SMOOTH;
+        let mut smooth = None;
+        if let Some(val) = data.get(key) {
+            let path = sub_path("", key);
+            checker.unknown_keys(&path, val, &["tension", "corner_angle", "strokes"]);
+            let mut setting = SmoothSetting::default();
+            for (key, range, target) in [
+                ("tension", 0.0..=1.0, &mut setting.tension),
+                ("corner_angle", 0.0..=180.0, &mut setting.corner_angle),
+            ] {
+                let path = sub_path(&path, key);
+                match val.get(key).and_then(|val| checker.number(&path, val)) {
+                    Some(v) if range.contains(&v) => *target = v,
+                    Some(v) => checker.issue(path, format!("`{v}` is out of {range:?}!")),
+                    None => {}
+                }
+            }
+            if let Some(val) = val.get("strokes") {
+                let path = sub_path(&path, "strokes");
+                match val.as_object() {
+                    Some(obj) => {
+                        for (stroke, val) in obj {
+                            let path = sub_path(&path, stroke);
+                            match checker.number(&path, val) {
+                                Some(v) if (0.0..=1.0).contains(&v) => {
+                                    setting.strokes.insert(stroke.clone(), v);
+                                }
+                                Some(v) => {
+                                    checker.issue(path, format!("`{v}` is out of 0.0..=1.0!"))
+                                }
+                                None => {}
+                            }
+                        }
+                    }
+                    None => checker.issue(path, format!("Expected an object, found `{val}`!")),
+                }
+            }
+            smooth = Some(setting);
+        }
+
+        let key = keys::STRICTNESS;
+        let strictness = data
+            .get(key)
+            .and_then(|val| checker.parse(&sub_path("", key), val))
+            .unwrap_or_default();
+        if strictness == Strictness::Deny && !checker.issues.is_empty() {
+            let issues: Vec<String> = checker.issues.iter().map(|i| i.to_string()).collect();
+            return Err(serde::de::Error::custom(format!(
+                "Config Error: {} issues!\n{}",
+                issues.len(),
+                issues.join("\n")
+            )));
+        }
+
         Ok(Self {
             size,
             units,
@@ -209,7 +494,20 @@ impl<'de> Deserialize<'de> for Config {
             supplement,
             type_replace,
             place_replace,
+            reduce_trigger,
+            replace_trigger,
+            visual_corr,
+            sharpness,
+            reduce_replace,
+            space_ctrls,
+            space_assign,
+            interval,
+            main_edge,
+            smooth,
+            strictness,
+            issues: checker.issues,
             data,
+            revision: REVISION.fetch_add(1, atomic::Ordering::Relaxed),
         })
     }
 }
@@ -239,97 +537,133 @@ impl Default for Config {
 }
 
 impl Config {
+    pub fn issues(&self) -> &[ConfigIssue] {
+        &self.issues
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
     pub fn get_reduce_trigger(&self, axis: Axis) -> f32 {
-        self.data
-            .get(keys::REDUCE_TRIGGER)
-            .and_then(|v| v.as_f64().or(v.get(axis.symbol()).and_then(|v| v.as_f64())))
-            .unwrap_or(0.0) as f32
+        *self.reduce_trigger.hv_get(axis)
     }
 
     pub fn get_replace_trigger(&self, axis: Axis) -> f32 {
-        self.data
-            .get(keys::REPLACE_TRIGGER)
-            .and_then(|v| v.as_f64().or(v.get(axis.symbol()).and_then(|v| v.as_f64())))
-            .unwrap_or(0.0) as f32
+        *self.replace_trigger.hv_get(axis)
     }
 
     pub fn get_visual_corr(&self, axis: Axis) -> f32 {
-        self.data
-            .get(keys::VISUAL_CORR)
-            .and_then(|v| v.as_f64().or(v.get(axis.symbol()).and_then(|v| v.as_f64())))
-            .unwrap_or(0.0) as f32
+        *self.visual_corr.hv_get(axis)
+    }
+
+    pub fn get_sharpness(&self, axis: Axis) -> view::SharpnessModel {
+        *self.sharpness.hv_get(axis)
     }
 
     pub fn get_space_ctrls(&self) -> (Vec<&str>, Option<&sj::value::Map<String, sj::Value>>) {
-        let obj = self.data.get(keys::SPACE_CTRLS).and_then(|d| d.as_object());
-        let order = obj
-            .and_then(|obj| obj.get("order"))
-            .and_then(|order| order.as_array())
-            .and_then(|list| list.iter().map(|v| v.as_str()).collect())
-            .unwrap_or_default();
-        (order, obj)
+        (
+            self.space_ctrls.order.iter().map(|s| s.as_str()).collect(),
+            self.space_ctrls.settings.as_ref(),
+        )
     }
 
     pub fn get_space_assign_settings(
         &self,
     ) -> (DataHV<[f32; 2]>, DataHV<[f32; 2]>, DataHV<Option<f32>>) {
-        let mut settings = (
-            DataHV::splat([1.0; 2]),
-            DataHV::splat([0.0; 2]),
-            DataHV::splat(None),
-        );
+        (
+            self.space_assign.white,
+            self.space_assign.visual_corr,
+            self.space_assign.unit,
+        )
+    }
+
+    pub fn get_space_mode(&self) -> SpaceMode {
+        self.space_assign.mode
+    }
+
+    pub fn get_symmetry(&self) -> bool {
+        self.space_assign.symmetry
+    }
+
+    // `None` if the paths are not smoothed.
+    pub fn get_smooth(&self) -> Option<&SmoothSetting> {
+        self.smooth.as_ref()
+    }
+
+    pub fn get_interval_limit(&self, axis: Axis) -> Option<f32> {
+        *self.interval.limit.hv_get(axis)
+    }
+
+    pub fn get_interval_rules(&self) -> &[IntervalMatch] {
+        &self.interval.rules
+    }
 
-        if let Some(data) = self.data.get(keys::SPACE_ASSIGN) {
-            if let Some(data) = data.get("white") {
-                settings
-                    .0
-                    .as_mut()
-                    .zip(get_axis_side_val(data))
-                    .into_iter()
-                    .for_each(|(setting, val)| {
-                        *setting = val.map(|v| v.and_then(|v| v.as_f64()).unwrap_or(1.0) as f32);
-                    });
+    pub fn get_main_edge(&self) -> &[EdgeCheck<bool>] {
+        &self.main_edge
+    }
+
+    // `file` paths are relative to `dir`, the directory of the fas file. Expressions are
+    // evaluated after the sets they refer to.
+    pub fn get_charsets(&self, dir: Option<&Path>) -> Result<CharSets, CharSetError> {
+        fn resolve(
+            name: &str,
+            exprs: &BTreeMap<&str, &str>,
+            sets: &mut CharSets,
+            stack: &mut Vec<String>,
+        ) -> Result<(), CharSetError> {
+            if sets.contains_key(name) {
+                return Ok(());
             }
-            if let Some(data) = data.get("visual_corr") {
-                settings
-                    .1
-                    .as_mut()
-                    .zip(get_axis_side_val(data))
-                    .into_iter()
-                    .for_each(|(setting, val)| {
-                        *setting = val.map(|v| v.and_then(|v| v.as_f64()).unwrap_or(0.0) as f32);
-                    });
+            if stack.iter().any(|n| n == name) {
+                return Err(CharSetError::Cycle(name.to_string()));
             }
-            if let Some(data) = data.get("unit") {
-                settings
-                    .2
-                    .as_mut()
-                    .zip(get_axis_val(data))
-                    .into_iter()
-                    .for_each(|(setting, val)| {
-                        *setting = val.and_then(|v| v.as_f64()).map(|v| v as f32);
-                    });
+            stack.push(name.to_string());
+            for dep in CharSets::names_in(exprs[name]) {
+                if exprs.contains_key(dep.as_str()) {
+                    resolve(&dep, exprs, sets, stack)?;
+                }
             }
+            let set = sets.eval(exprs[name])?;
+            sets.insert(name.to_string(), set);
+            stack.pop();
+            Ok(())
         }
 
-        settings
-    }
-
-    pub fn get_interval_limit(&self, axis: Axis) -> Option<f32> {
-        return self
-            .data
-            .get(keys::INTERVAL)
-            .and_then(|val| val.get("limit"))
-            .and_then(|val| get_axis_val(val).hv_get(axis).and_then(|val| val.as_f64()))
-            .map(|val| val as f32);
+        let mut sets = CharSets::default();
+        if let Some(obj) = self.data.get(keys::CHARSETS).and_then(|v| v.as_object()) {
+            let mut exprs = BTreeMap::new();
+            for (name, val) in obj {
+                if let Some(chars) = val.get("chars").and_then(|v| v.as_str()) {
+                    sets.insert(name.clone(), CharSet::from_text(chars));
+                } else if let Some(path) = val.get("file").and_then(|v| v.as_str()) {
+                    let set = match dir {
+                        Some(dir) => CharSet::from_file(dir.join(path))?,
+                        None => CharSet::from_file(path)?,
+                    };
+                    sets.insert(name.clone(), set);
+                } else if let Some(expr) = val.get("expr").and_then(|v| v.as_str()) {
+                    exprs.insert(name.as_str(), expr);
+                } else {
+                    log::warn!("Unknown definition of char set `{name}`: {val}");
+                }
+            }
+            for name in exprs.keys() {
+                resolve(name, &exprs, &mut sets, &mut vec![])?;
+            }
+        }
+        Ok(sets)
     }
 
     pub fn reduce_replace_name(&self, axis: Axis, name: &str) -> Option<&str> {
-        self.data
-            .get(keys::REDUCE_REPLACE)
-            .and_then(|table| table.get(axis.symbol()))
-            .and_then(|table| table.get(name))
-            .and_then(|r| r.as_str())
+        self.reduce_replace
+            .hv_get(axis)
+            .get(name)
+            .map(|r| r.as_str())
     }
 
     fn type_replace_name(&self, name: &str, in_tp: (CstType, Section)) -> Option<String> {
@@ -353,14 +687,33 @@ impl Config {
             .cloned()
     }
 
-    fn place_replace_name(&self, name: &str, places: DataHV<[bool; 2]>) -> Option<String> {
+    fn place_replace_name(
+        &self,
+        name: &str,
+        places: DataHV<[bool; 2]>,
+        tracer: &mut Tracer,
+    ) -> Option<String> {
         self.place_replace
             .get(name)
             .and_then(|pm| {
-                pm.iter().find_map(|(r, c)| match place_match(r, places) {
-                    true => Some(c),
-                    false => None,
-                })
+                pm.iter()
+                    .enumerate()
+                    .find_map(|(i, (r, c))| match place_match(r, places) {
+                        true => {
+                            tracer.record("", name, || TraceEvent::PlaceReplace {
+                                rule: i,
+                                name: c.clone(),
+                                candidates: pm
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(_, (r, _))| place_match(r, places))
+                                    .map(|(j, _)| j)
+                                    .collect(),
+                            });
+                            Some(c)
+                        }
+                        false => None,
+                    })
             })
             .cloned()
     }
@@ -370,18 +723,37 @@ impl Config {
         name: &str,
         in_tp: (CstType, Section),
         adjacency: DataHV<[bool; 2]>,
+        tracer: &mut Tracer,
     ) -> Option<String> {
         match self.type_replace_name(name, in_tp) {
-            Some(comp) => self.place_replace_name(&comp, adjacency).or(Some(comp)),
-            None => self.place_replace_name(name, adjacency),
+            Some(comp) => {
+                tracer.record("", name, || TraceEvent::TypeReplace {
+                    tp: in_tp.0.symbol(),
+                    section: in_tp.1,
+                    name: comp.clone(),
+                });
+                self.place_replace_name(&comp, adjacency, tracer)
+                    .or(Some(comp))
+            }
+            None => self.place_replace_name(name, adjacency, tracer),
         }
     }
 
+    pub fn get_type_replace(&self) -> &BTreeMap<char, BTreeMap<Section, BTreeMap<String, String>>> {
+        &self.type_replace
+    }
+
+    pub fn get_place_replace(&self) -> &BTreeMap<String, Vec<(String, String)>> {
+        &self.place_replace
+    }
+
     fn set_main_comp_axis_in_setting(
         &self,
         comps: &mut Vec<StrucComb>,
+        paths: &[String],
         axis: Axis,
         len_list: &Vec<usize>,
+        tracer: &mut Tracer,
     ) -> Vec<[Option<bool>; 2]> {
         let mut status = vec![[Option::<bool>::None; 2]; comps.len()];
         let mut edge_datas: Vec<Vec<Option<view::EdgeShape>>> = vec![vec![None; 4]; comps.len()];
@@ -407,105 +779,98 @@ impl Config {
             edge_datas[i][j].clone().unwrap()
         }
 
-        match self
-            .data
-            .get(keys::MAIN_EDGE)
-            .map(|val| sj::from_value::<Vec<EdgeCheck<bool>>>(val.clone()))
-        {
-            Some(Ok(settings)) => {
-                for mcheck in settings.iter() {
-                    let mut indexs: Vec<usize> = (0..comps.len()).collect();
-                    let force = mcheck
-                        .get_value::<bool>("force")
-                        .map(|r| {
-                            r.unwrap_or_else(|e| {
-                                eprint!("Main edge check `fore`: {e}");
-                                false
-                            })
-                        })
-                        .unwrap_or(false);
-
-                    if !force {
-                        indexs.retain(|i| {
-                            status[*i]
-                                .iter()
-                                .zip(mcheck.setup.iter())
-                                .all(|(a, b)| a.is_none() || b.is_none())
-                        })
-                    }
+        for j in priority_order(&self.main_edge) {
+            let mcheck = &self.main_edge[j];
+            let mut indexs: Vec<usize> = (0..comps.len()).collect();
+            let force = mcheck
+                .get_value::<bool>("force")
+                .map(|r| {
+                    r.unwrap_or_else(|e| {
+                        eprint!("Main edge check `fore`: {e}");
+                        false
+                    })
+                })
+                .unwrap_or(false);
+
+            if !force {
+                indexs.retain(|i| {
+                    status[*i]
+                        .iter()
+                        .zip(mcheck.setup.iter())
+                        .all(|(a, b)| a.is_none() || b.is_none())
+                })
+            }
 
-                    let mut not_set = None;
-                    for i in indexs {
-                        let r = mcheck.is_match(
-                            axis,
-                            i,
-                            length,
-                            |i, axis, side| get_edge(i, axis, side, &mut edge_datas, comps),
-                            |k, v| match k {
-                                "not_set" => {
-                                    let b = v.as_bool().unwrap_or(true);
-                                    let r = *not_set.get_or_insert_with(|| {
-                                        (0..2).all(|j| {
-                                            mcheck.setup[j].is_none()
-                                                || !status
-                                                    .iter()
-                                                    .any(|s| matches!(s[j], Some(true)))
-                                        })
-                                    });
-                                    Ok(r == b)
-                                }
-                                "state_f" | "state_b" => {
-                                    let b = v.as_bool();
-                                    let side = match k {
-                                        "state_f" => 0,
-                                        "state_b" => 1,
-                                        _ => unreachable!(),
-                                    };
-                                    Ok(status[i][side] == b)
-                                }
-                                "state_front_f" | "state_front_b" => {
-                                    let b = v.as_bool();
-                                    let side = match k {
-                                        "state_front_f" => 0,
-                                        "state_front_b" => 1,
-                                        _ => unreachable!(),
-                                    };
-                                    Ok(i != 0 && status[i - 1][side] == b)
-                                }
-                                "state_back_f" | "state_back_b" => {
-                                    let b = v.as_bool();
-                                    let side = match k {
-                                        "state_back_f" => 0,
-                                        "state_back_b" => 1,
-                                        _ => unreachable!(),
-                                    };
-                                    Ok(i + 1 != length && status[i + 1][side] == b)
-                                }
-                                "is_max" => {
-                                    let b = v.as_bool().unwrap_or(true);
-                                    Ok((len_list[i] == max_len) == b)
-                                }
-                                _ => Err(CheckError::UnknowKey(k.to_string())),
-                            },
-                        );
-
-                        match r {
-                            Ok(r) => {
-                                if r {
-                                    for j in 0..2 {
-                                        if mcheck.setup[j].is_some() {
-                                            status[i][j] = mcheck.setup[j];
-                                        }
-                                    }
+            let mut not_set = None;
+            for i in indexs {
+                let r = mcheck.is_match(
+                    axis,
+                    i,
+                    length,
+                    |i, axis, side| get_edge(i, axis, side, &mut edge_datas, comps),
+                    |k, v| match k {
+                        "not_set" => {
+                            let b = v.as_bool().unwrap_or(true);
+                            let r = *not_set.get_or_insert_with(|| {
+                                (0..2).all(|j| {
+                                    mcheck.setup[j].is_none()
+                                        || !status.iter().any(|s| matches!(s[j], Some(true)))
+                                })
+                            });
+                            Ok(r == b)
+                        }
+                        "state_f" | "state_b" => {
+                            let b = v.as_bool();
+                            let side = match k {
+                                "state_f" => 0,
+                                "state_b" => 1,
+                                _ => unreachable!(),
+                            };
+                            Ok(status[i][side] == b)
+                        }
+                        "state_front_f" | "state_front_b" => {
+                            let b = v.as_bool();
+                            let side = match k {
+                                "state_front_f" => 0,
+                                "state_front_b" => 1,
+                                _ => unreachable!(),
+                            };
+                            Ok(i != 0 && status[i - 1][side] == b)
+                        }
+                        "state_back_f" | "state_back_b" => {
+                            let b = v.as_bool();
+                            let side = match k {
+                                "state_back_f" => 0,
+                                "state_back_b" => 1,
+                                _ => unreachable!(),
+                            };
+                            Ok(i + 1 != length && status[i + 1][side] == b)
+                        }
+                        "is_max" => {
+                            let b = v.as_bool().unwrap_or(true);
+                            Ok((len_list[i] == max_len) == b)
+                        }
+                        _ => Err(CheckError::UnknowKey(k.to_string())),
+                    },
+                );
+
+                match r {
+                    Ok(r) => {
+                        if r {
+                            tracer.record(&paths[i], &comps[i].name, || TraceEvent::MainEdge {
+                                axis,
+                                rule: j,
+                            });
+                            for k in 0..2 {
+                                if mcheck.setup[k].is_some() {
+                                    status[i][k] = mcheck.setup[k];
                                 }
                             }
-                            Err(e) => eprintln!("In main edge setting: {e}"),
                         }
                     }
+                    Err(e) => eprintln!("In main edge setting: {e}"),
                 }
             }
-            Some(Err(e)) => eprintln!("Error in Main Edge settings: {e}"),
-            None => {}
         }
 
         status
@@ -717,13 +1082,16 @@ impl Config {
     //     return status;
     // }
 
+    // `paths` are the node paths of `comps` in the traced node.
     pub fn set_main_comp_axis(
         &self,
         comps: &mut Vec<StrucComb>,
+        paths: &[String],
         axis: Axis,
         len_list: &Vec<usize>,
+        tracer: &mut Tracer,
     ) {
-        let mut status = self.set_main_comp_axis_in_setting(comps, axis, len_list);
+        let mut status = self.set_main_comp_axis_in_setting(comps, paths, axis, len_list, tracer);
 
         for i in 0..comps.len() {
             if comps[i]
@@ -777,38 +1145,58 @@ impl Config {
             });
     }
 
-    pub fn set_intervals_axis(&self, comps: &mut Vec<StrucComb>, axis: Axis) -> Option<Vec<usize>> {
+    pub fn set_intervals_axis(
+        &self,
+        comps: &mut Vec<StrucComb>,
+        paths: &[String],
+        axis: Axis,
+        tracer: &mut Tracer,
+    ) -> Option<Vec<usize>> {
         let mut intervals = Vec::with_capacity(comps.len() - 1);
         for i1 in 0..comps.len() - 1 {
             let i2 = i1 + 1;
             let edge1 = comps[i1].get_edge(axis, Side::Back, true).to_shape();
             let edge2 = comps[i2].get_edge(axis, Side::Front, true).to_shape();
             let mut val = 0;
+            let mut source = None;
 
             let (l, r) = comps.split_at_mut(i2);
             let r = StrucComb::set_edge_alloc(&mut l[i1], &edge1, &mut r[0], &edge2, axis).ok()?;
 
             if let Some(i_val) = r {
                 val = i_val;
-            } else if let Some(rules) = self
-                .data
-                .get(keys::INTERVAL)
-                .and_then(|v| v.get("rules"))
-                .and_then(|v| v.as_array())
-                .map(|v| {
-                    v.iter()
-                        .filter_map(|v| sj::from_value::<IntervalMatch>(v.clone()).ok())
-                        .collect::<Vec<IntervalMatch>>()
-                })
+            } else if let Some((j, i_val)) = self
+                .interval
+                .rules
+                .iter()
+                .enumerate()
+                .find_map(|(j, rule)| rule.is_match(&edge1, &edge2, axis).map(|v| (j, v)))
             {
-                if let Some(i_val) = rules
-                    .iter()
-                    .find_map(|rule| rule.is_match(&edge1, &edge2, axis))
-                {
-                    val = i_val;
-                }
+                val = i_val;
+                source = Some(j);
             };
 
+            tracer.record(&paths[i1], &comps[i1].name, || TraceEvent::Interval {
+                axis,
+                next: comps[i2].name.clone(),
+                val,
+                source: match (r, source) {
+                    (Some(_), _) => IntervalSource::Alloc,
+                    (None, Some(index)) => IntervalSource::Rule {
+                        index,
+                        note: self.interval.rules[index].note.clone(),
+                        candidates: self
+                            .interval
+                            .rules
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, rule)| rule.is_match(&edge1, &edge2, axis).is_some())
+                            .map(|(j, _)| j)
+                            .collect(),
+                    },
+                    (None, None) => IntervalSource::Default,
+                },
+            });
             intervals.push(val);
         }
 
@@ -874,6 +1262,178 @@ mod tests {
         assert!(zimian.val_in(15) < 0.9);
     }
 
+    #[test]
+    fn test_typed_config() {
+        let data = json!({
+            "reduce_trigger": {"h": 0.1, "v": "0.2"},
+            "visual_corr": 0.05,
+            "sharpness": {"h": {"model": "Weighted", "falloff": 2.0}},
+            "reduce_replace": {"h": {"口": "口字旁"}},
+            "space_assign": {"white": {"h": [0.5, 2.0]}, "unit": 0.01, "whtie": 1},
+            "space_ctrls": {"order": ["subarea", "trend"], "subarea": {}, "subcomp": {}},
+            "interval": {
+                "limit": {"v": 0.8},
+                "rules": [
+                    {"inverse": false, "axis": null, "val": 1, "note": "", "rule1": ["*", "*", "*"], "rule2": ["*", "*", "*"]},
+                    {"val": 1}
+                ]
+            },
+            "smooth": {"tension": 1.5, "corner_angle": 45, "strokes": {"3": 0.5, "1": 2}},
+            "zimain": [],
+        });
+        let cfg: Config = sj::from_value(data.clone()).unwrap();
+
+        assert_eq!(cfg.get_reduce_trigger(Axis::Horizontal), 0.1);
+        assert_eq!(cfg.get_reduce_trigger(Axis::Vertical), 0.0);
+        assert_eq!(cfg.get_visual_corr(Axis::Vertical), 0.05);
+        assert_eq!(
+            cfg.get_sharpness(Axis::Horizontal),
+            view::SharpnessModel::Weighted { falloff: 2.0 }
+        );
+        assert_eq!(
+            cfg.get_sharpness(Axis::Vertical),
+            view::SharpnessModel::ZeroOne
+        );
+        assert_eq!(
+            cfg.reduce_replace_name(Axis::Horizontal, "口"),
+            Some("口字旁")
+        );
+        assert_eq!(cfg.reduce_replace_name(Axis::Vertical, "口"), None);
+        let (white, _, unit) = cfg.get_space_assign_settings();
+        assert_eq!(white.h, [0.5, 2.0]);
+        assert_eq!(white.v, [1.0, 1.0]);
+        assert_eq!(unit.v, Some(0.01));
+        assert_eq!(cfg.get_space_ctrls().0, vec!["subarea", "trend"]);
+        assert_eq!(cfg.get_interval_limit(Axis::Horizontal), None);
+        assert_eq!(cfg.get_interval_limit(Axis::Vertical), Some(0.8));
+        assert_eq!(cfg.interval.rules.len(), 1);
+        assert_eq!(
+            cfg.get_smooth(),
+            Some(&SmoothSetting {
+                tension: 0.0,
+                corner_angle: 45.0,
+                strokes: BTreeMap::from([("3".to_string(), 0.5)]),
+            })
+        );
+        assert_eq!(cfg.get_smooth().unwrap().tension("3"), 0.5);
+        assert_eq!(cfg.get_smooth().unwrap().tension("2"), 0.0);
+
+        let paths: Vec<&str> = cfg.issues().iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/zimain",
+                "/reduce_trigger/v",
+                "/space_ctrls/order/1",
+                "/space_ctrls/subcomp",
+                "/space_assign/whtie",
+                "/interval/rules/1",
+                "/smooth/tension",
+                "/smooth/strokes/1",
+            ]
+        );
+
+        assert_eq!(sj::to_value(&cfg).unwrap(), data);
+    }
+
+    #[test]
+    fn test_charsets() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("list.txt"), "# list\n甲乙丙").unwrap();
+        let cfg: Config = sj::from_value(json!({
+            "charsets": {
+                "a-all": { "expr": "b-part | list" },
+                "b-part": { "expr": "list - c" },
+                "c": { "chars": "丙" },
+                "list": { "file": "list.txt" }
+            }
+        }))
+        .unwrap();
+
+        let sets = cfg.get_charsets(Some(dir.path())).unwrap();
+        assert_eq!(sets["b-part"], CharSet::from_text("甲乙"));
+        assert_eq!(sets["a-all"], CharSet::from_text("甲乙丙"));
+        assert!(matches!(
+            cfg.get_charsets(None),
+            Err(CharSetError::Io { .. })
+        ));
+
+        let cfg: Config = sj::from_value(json!({
+            "charsets": { "a": { "expr": "b" }, "b": { "expr": "URO & a" } }
+        }))
+        .unwrap();
+        assert!(matches!(cfg.get_charsets(None), Err(CharSetError::Cycle(name)) if name == "a"));
+    }
+
+    #[test]
+    fn test_rule_check() {
+        let data = json!({
+            "space_ctrls": {
+                "order": ["trend"],
+                "trend": {"scale": {"h": [
+                    {"conditions": {"edge1": ["*", "*", "*"], "not": {"state_f": true}}, "setup": [1.0, null]}
+                ]}}
+            },
+            "interval": {
+                "rules": [
+                    {"inverse": false, "axis": null, "val": 1, "note": "", "rule1": ["*", "?", "*"], "rule2": ["*", "*", "*"]}
+                ]
+            },
+            "main_edge": [
+                {"conditions": {"any": [{"state_f": null}, {"is_max": 1}]}, "setup": [true, null], "force": true},
+                {"conditions": {"section": "Begin", "all": {}}, "setup": [null, true], "focre": true}
+            ]
+        });
+        let cfg: Config = sj::from_value(data.clone()).unwrap();
+        assert_eq!(cfg.strictness(), Strictness::Warn);
+        assert!(cfg.get_interval_rules().is_empty());
+        assert_eq!(cfg.get_main_edge().len(), 2);
+
+        let paths: Vec<&str> = cfg.issues().iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/space_ctrls/trend/scale/h/0/conditions/not/state_f",
+                "/interval/rules/0/rule1",
+                "/main_edge/0/conditions/any/1/is_max",
+                "/main_edge/1/focre",
+                "/main_edge/1/conditions/all",
+                "/main_edge/1/conditions/section",
+            ]
+        );
+
+        let mut data = data;
+        data["strictness"] = json!("Deny");
+        assert!(sj::from_value::<Config>(data).is_err());
+    }
+
+    #[test]
+    fn test_value_issues() {
+        let data = json!({
+            "size": {"h": "big", "v": 0.8},
+            "units": {"h": [0.1, "x"]},
+            "zimian": [[0, 0.9]],
+            "supplement": {"一": 1},
+            "type_replace": [],
+            "place_replace": {"一": [["x", "一横"]]}
+        });
+        let cfg: Config = sj::from_value(data.clone()).unwrap();
+        assert_eq!(cfg.size, DataHV::new(1.0, 0.8));
+        assert_eq!(cfg.units, DataHV::splat(vec![DEFAULT_MIN_VALUE]));
+        assert_eq!(cfg.zimian.h.val_in(0), 0.9);
+        assert!(cfg.supplement.is_empty());
+
+        let paths: Vec<&str> = cfg.issues().iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["/size/h", "/units/h", "/supplement", "/type_replace"]
+        );
+
+        let mut data = data;
+        data["strictness"] = json!("Deny");
+        assert!(sj::from_value::<Config>(data).is_err());
+    }
+
     #[test]
     fn test_type_replace_name() {
         let mut cfg = Config::default();
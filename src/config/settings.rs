@@ -0,0 +1,232 @@
+use crate::base::*;
+
+use serde::{Deserialize, Serialize};
+use serde_json as sj;
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct ConfigIssue {
+    pub path: String,
+    pub msg: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}`: {}", self.path, self.msg)
+    }
+}
+
+// How config issues are handled when loading.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Strictness {
+    Ignore,
+    #[default]
+    Warn,
+    // Refuses the config if there is any issue.
+    Deny,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SpaceMode {
+    // Splits the space top-down with `reallocate_on_weights`.
+    #[default]
+    Greedy,
+    // Solves all the space of an axis jointly instead of the space checks, see `service::solver`.
+    Solver,
+}
+
+#[derive(Clone)]
+pub struct SpaceAssign {
+    pub white: DataHV<[f32; 2]>,
+    pub visual_corr: DataHV<[f32; 2]>,
+    pub unit: DataHV<Option<f32>>,
+    pub mode: SpaceMode,
+    // Gives symmetric allocations and identical siblings the same values after the assignment.
+    pub symmetry: bool,
+}
+
+impl Default for SpaceAssign {
+    fn default() -> Self {
+        Self {
+            white: DataHV::splat([1.0; 2]),
+            visual_corr: DataHV::splat([0.0; 2]),
+            unit: DataHV::splat(None),
+            mode: SpaceMode::default(),
+            symmetry: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SmoothSetting {
+    // 0 gives Catmull-Rom curves and 1 straight lines.
+    pub tension: f32,
+    // Turns sharper than this, in degrees, stay corners.
+    pub corner_angle: f32,
+    // Tension of the strokes of a style, keyed by their directions as in `StrucProto::strokes`.
+    pub strokes: std::collections::BTreeMap<String, f32>,
+}
+
+impl Default for SmoothSetting {
+    fn default() -> Self {
+        Self {
+            tension: 0.0,
+            corner_angle: 60.0,
+            strokes: Default::default(),
+        }
+    }
+}
+
+impl SmoothSetting {
+    pub fn tension(&self, stroke: &str) -> f32 {
+        self.strokes.get(stroke).copied().unwrap_or(self.tension)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct IntervalSetting {
+    pub limit: DataHV<Option<f32>>,
+    pub rules: Vec<super::IntervalMatch>,
+}
+
+#[derive(Clone, Default)]
+pub struct SpaceCtrls {
+    pub order: Vec<String>,
+    pub settings: Option<sj::value::Map<String, sj::Value>>,
+}
+
+// Collects problems found while parsing the config, paths are JSON pointers.
+#[derive(Default)]
+pub struct Checker {
+    pub issues: Vec<ConfigIssue>,
+}
+
+pub fn sub_path(path: &str, key: impl std::fmt::Display) -> String {
+    format!("{path}/{key}")
+}
+
+impl Checker {
+    pub fn issue(&mut self, path: String, msg: impl ToString) {
+        self.issues.push(ConfigIssue {
+            path,
+            msg: msg.to_string(),
+        });
+    }
+
+    pub fn unknown_keys(&mut self, path: &str, value: &sj::Value, known: &[&str]) {
+        if let Some(obj) = value.as_object() {
+            obj.keys()
+                .filter(|k| !known.contains(&k.as_str()))
+                .for_each(|k| self.issue(sub_path(path, k), "Unknown key!"));
+        }
+    }
+
+    pub fn parse<T: serde::de::DeserializeOwned>(
+        &mut self,
+        path: &str,
+        value: &sj::Value,
+    ) -> Option<T> {
+        match sj::from_value(value.clone()) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                self.issue(path.to_string(), e);
+                None
+            }
+        }
+    }
+
+    pub fn number(&mut self, path: &str, value: &sj::Value) -> Option<f32> {
+        match value.as_f64() {
+            Some(val) => Some(val as f32),
+            None => {
+                self.issue(
+                    path.to_string(),
+                    format!("Expected a number, found `{value}`!"),
+                );
+                None
+            }
+        }
+    }
+
+    // `0.1` or `{"h": 0.1, "v": 0.2}`
+    pub fn axis_number(&mut self, path: &str, value: &sj::Value) -> DataHV<Option<f32>> {
+        match value {
+            sj::Value::Object(_) => {
+                self.unknown_keys(path, value, &["h", "v"]);
+                Axis::hv().into_map(|axis| {
+                    value
+                        .get(axis.symbol())
+                        .and_then(|val| self.number(&sub_path(path, axis.symbol()), val))
+                })
+            }
+            val => DataHV::splat(self.number(path, val)),
+        }
+    }
+
+    // `value` or `{"h": value, "v": value}`
+    pub fn axis_parse<T: serde::de::DeserializeOwned + Clone>(
+        &mut self,
+        path: &str,
+        value: &sj::Value,
+    ) -> DataHV<Option<T>> {
+        match value {
+            sj::Value::Object(_) => {
+                self.unknown_keys(path, value, &["h", "v"]);
+                Axis::hv().into_map(|axis| {
+                    value
+                        .get(axis.symbol())
+                        .and_then(|val| self.parse(&sub_path(path, axis.symbol()), val))
+                })
+            }
+            val => DataHV::splat(self.parse(path, val)),
+        }
+    }
+
+    fn side_number(&mut self, path: &str, value: &sj::Value) -> [Option<f32>; 2] {
+        match value {
+            sj::Value::Array(list) => {
+                if list.len() > 2 {
+                    self.issue(path.to_string(), "More than two values!");
+                }
+                [0, 1].map(|i| {
+                    list.get(i)
+                        .and_then(|val| self.number(&sub_path(path, i), val))
+                })
+            }
+            val => [self.number(path, val); 2],
+        }
+    }
+
+    // `0.1`, `[0.1, 0.2]` or `{"h": ..., "v": ...}`
+    pub fn axis_side_number(&mut self, path: &str, value: &sj::Value) -> DataHV<[Option<f32>; 2]> {
+        match value {
+            sj::Value::Object(_) => {
+                self.unknown_keys(path, value, &["h", "v"]);
+                Axis::hv().into_map(|axis| match value.get(axis.symbol()) {
+                    Some(val) => self.side_number(&sub_path(path, axis.symbol()), val),
+                    None => [None; 2],
+                })
+            }
+            val => DataHV::splat(self.side_number(path, val)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checker() {
+        let mut checker = Checker::default();
+        let val = checker.axis_number("/a", &sj::json!({"h": 0.1, "x": 1}));
+        assert_eq!(val.h, Some(0.1));
+        assert_eq!(val.v, None);
+        assert_eq!(checker.issues[0].path, "/a/x");
+
+        let val = checker.axis_side_number("/b", &sj::json!({"v": [0.5, "1"]}));
+        assert_eq!(val.h, [None; 2]);
+        assert_eq!(val.v, [Some(0.5), None]);
+        assert_eq!(checker.issues[1].path, "/b/v/1");
+        assert_eq!(checker.issues.len(), 2);
+    }
+}
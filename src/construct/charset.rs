@@ -0,0 +1,519 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::{Deref, DerefMut, RangeInclusive},
+};
+
+// Sets defined by the national standards, resolved by `CharSets::get_set`. The list of
+// 通用规范汉字表 isn't bundled, so its tiers and the GB 18030 level 2 built on it are left to
+// `charsets` in the config.
+pub const BUILTIN_SETS: [&str; 9] = [
+    "gb2312",
+    "gb2312-1",
+    "gb2312-2",
+    "gbk",
+    "gb18030-1",
+    "gb18030-3",
+    "big5",
+    "big5-1",
+    "big5-2",
+];
+
+#[derive(Debug)]
+pub enum CharSetError {
+    Io { path: String, msg: String },
+    UnknownSet(String),
+    Cycle(String),
+    Syntax { expr: String, pos: usize },
+}
+
+impl std::fmt::Display for CharSetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, msg } => write!(f, "Failed to read char set `{path}`: {msg}"),
+            Self::UnknownSet(name) => write!(f, "Unknown char set `{name}`!"),
+            Self::Cycle(name) => write!(f, "Char set `{name}` depends on itself!"),
+            Self::Syntax { expr, pos } => {
+                write!(f, "Syntax error in char set expression `{expr}` at {pos}!")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CharSetError {}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Debug)]
+pub enum UnicodeBlock {
+    RadicalsSupplement,
+    KangxiRadicals,
+    Strokes,
+    ExtA,
+    Uro,
+    Compatibility,
+    ExtB,
+    ExtC,
+    ExtD,
+    ExtE,
+    ExtF,
+    ExtI,
+    CompatibilitySupplement,
+    ExtG,
+    ExtH,
+    ExtJ,
+}
+
+impl UnicodeBlock {
+    pub fn list() -> [UnicodeBlock; 16] {
+        [
+            Self::RadicalsSupplement,
+            Self::KangxiRadicals,
+            Self::Strokes,
+            Self::ExtA,
+            Self::Uro,
+            Self::Compatibility,
+            Self::ExtB,
+            Self::ExtC,
+            Self::ExtD,
+            Self::ExtE,
+            Self::ExtF,
+            Self::ExtI,
+            Self::CompatibilitySupplement,
+            Self::ExtG,
+            Self::ExtH,
+            Self::ExtJ,
+        ]
+    }
+
+    pub fn range(&self) -> std::ops::RangeInclusive<u32> {
+        match self {
+            Self::RadicalsSupplement => 0x2E80..=0x2EFF,
+            Self::KangxiRadicals => 0x2F00..=0x2FDF,
+            Self::Strokes => 0x31C0..=0x31EF,
+            Self::ExtA => 0x3400..=0x4DBF,
+            Self::Uro => 0x4E00..=0x9FFF,
+            Self::Compatibility => 0xF900..=0xFAFF,
+            Self::ExtB => 0x20000..=0x2A6DF,
+            Self::ExtC => 0x2A700..=0x2B73F,
+            Self::ExtD => 0x2B740..=0x2B81F,
+            Self::ExtE => 0x2B820..=0x2CEAF,
+            Self::ExtF => 0x2CEB0..=0x2EBEF,
+            Self::ExtI => 0x2EBF0..=0x2EE5F,
+            Self::CompatibilitySupplement => 0x2F800..=0x2FA1F,
+            Self::ExtG => 0x30000..=0x3134F,
+            Self::ExtH => 0x31350..=0x323AF,
+            Self::ExtJ => 0x323B0..=0x3347F,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::RadicalsSupplement => "Radicals",
+            Self::KangxiRadicals => "Kangxi",
+            Self::Strokes => "Strokes",
+            Self::ExtA => "ExtA",
+            Self::Uro => "URO",
+            Self::Compatibility => "Compat",
+            Self::ExtB => "ExtB",
+            Self::ExtC => "ExtC",
+            Self::ExtD => "ExtD",
+            Self::ExtE => "ExtE",
+            Self::ExtF => "ExtF",
+            Self::ExtI => "ExtI",
+            Self::CompatibilitySupplement => "CompatSup",
+            Self::ExtG => "ExtG",
+            Self::ExtH => "ExtH",
+            Self::ExtJ => "ExtJ",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        Self::list().into_iter().find(|b| b.symbol() == symbol)
+    }
+
+    pub fn classify(chr: char) -> Option<Self> {
+        let code = chr as u32;
+        Self::list().into_iter().find(|b| b.range().contains(&code))
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct CharSet(BTreeSet<char>);
+
+impl Deref for CharSet {
+    type Target = BTreeSet<char>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for CharSet {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl FromIterator<char> for CharSet {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl CharSet {
+    pub fn from_block(block: UnicodeBlock) -> Self {
+        block.range().filter_map(char::from_u32).collect()
+    }
+
+    // `#` starts a comment line, whitespace is ignored
+    pub fn from_text(text: &str) -> Self {
+        text.lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(|line| line.chars())
+            .filter(|chr| !chr.is_whitespace())
+            .collect()
+    }
+
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, CharSetError> {
+        std::fs::read_to_string(&path)
+            .map(|text| Self::from_text(&text))
+            .map_err(|e| CharSetError::Io {
+                path: path.as_ref().display().to_string(),
+                msg: e.to_string(),
+            })
+    }
+
+    pub fn union(&self, other: &CharSet) -> CharSet {
+        Self(&self.0 | &other.0)
+    }
+
+    pub fn intersection(&self, other: &CharSet) -> CharSet {
+        Self(&self.0 & &other.0)
+    }
+
+    pub fn difference(&self, other: &CharSet) -> CharSet {
+        Self(&self.0 - &other.0)
+    }
+
+    pub fn blocks(&self) -> BTreeMap<Option<UnicodeBlock>, usize> {
+        self.0.iter().fold(BTreeMap::new(), |mut map, chr| {
+            *map.entry(UnicodeBlock::classify(*chr)).or_default() += 1;
+            map
+        })
+    }
+}
+
+// The ideographs of the double byte codes in `codes` whose trail byte is in `trails`.
+fn double_byte_set(
+    encoding: &'static encoding_rs::Encoding,
+    codes: RangeInclusive<u16>,
+    trails: &[RangeInclusive<u8>],
+) -> CharSet {
+    codes
+        .map(u16::to_be_bytes)
+        .filter(|bytes| trails.iter().any(|t| t.contains(&bytes[1])))
+        .filter_map(|bytes| {
+            let text = encoding.decode_without_bom_handling_and_without_replacement(&bytes)?;
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(chr), None) if UnicodeBlock::classify(chr).is_some() => Some(chr),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+impl std::ops::BitOr for &CharSet {
+    type Output = CharSet;
+    fn bitor(self, rhs: Self) -> CharSet {
+        self.union(rhs)
+    }
+}
+
+impl std::ops::BitAnd for &CharSet {
+    type Output = CharSet;
+    fn bitand(self, rhs: Self) -> CharSet {
+        self.intersection(rhs)
+    }
+}
+
+impl std::ops::Sub for &CharSet {
+    type Output = CharSet;
+    fn sub(self, rhs: Self) -> CharSet {
+        self.difference(rhs)
+    }
+}
+
+// Named sets: the Unicode blocks and `BUILTIN_SETS` are always available, others are user
+// defined. User definitions take precedence.
+#[derive(Clone, Default)]
+pub struct CharSets(BTreeMap<String, CharSet>);
+
+impl Deref for CharSets {
+    type Target = BTreeMap<String, CharSet>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for CharSets {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl CharSets {
+    pub fn get_set(&self, name: &str) -> Result<CharSet, CharSetError> {
+        match self.0.get(name) {
+            Some(set) => Ok(set.clone()),
+            None => match UnicodeBlock::from_symbol(name) {
+                Some(block) => Ok(CharSet::from_block(block)),
+                None => self.builtin(name),
+            },
+        }
+    }
+
+    fn builtin(&self, name: &str) -> Result<CharSet, CharSetError> {
+        use UnicodeBlock::*;
+
+        const GB_TRAILS: [RangeInclusive<u8>; 1] = [0xA1..=0xFE];
+        const BIG5_TRAILS: [RangeInclusive<u8>; 2] = [0x40..=0x7E, 0xA1..=0xFE];
+        let blocks = |list: &[UnicodeBlock]| {
+            list.iter()
+                .fold(CharSet::default(), |set, b| &set | &CharSet::from_block(*b))
+        };
+        let ext_bf = [ExtB, ExtC, ExtD, ExtE, ExtF];
+
+        let set = match name {
+            "gb2312-1" => double_byte_set(encoding_rs::GBK, 0xB0A1..=0xD7FE, &GB_TRAILS),
+            "gb2312-2" => double_byte_set(encoding_rs::GBK, 0xD8A1..=0xF7FE, &GB_TRAILS),
+            "gb2312" => &self.get_set("gb2312-1")? | &self.get_set("gb2312-2")?,
+            "gbk" => double_byte_set(
+                encoding_rs::GBK,
+                0x8140..=0xFEFE,
+                &[0x40..=0x7E, 0x80..=0xFE],
+            ),
+            // The two byte region with URO and Ext A.
+            "gb18030-1" => &self.get_set("gbk")? | &blocks(&[Uro, ExtA]),
+            "gb18030-3" => &self.get_set("gb18030-1")? | &blocks(&ext_bf),
+            "big5-1" => double_byte_set(encoding_rs::BIG5, 0xA440..=0xC67E, &BIG5_TRAILS),
+            "big5-2" => double_byte_set(encoding_rs::BIG5, 0xC940..=0xF9D5, &BIG5_TRAILS),
+            "big5" => &self.get_set("big5-1")? | &self.get_set("big5-2")?,
+            _ => return Err(CharSetError::UnknownSet(name.to_string())),
+        };
+        Ok(set)
+    }
+
+    // The set names that `expr` refers to.
+    pub fn names_in(expr: &str) -> Vec<String> {
+        tokenize(expr)
+            .into_iter()
+            .filter_map(|(_, token)| match token {
+                Token::Name(name) => Some(name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Operators `|`, `&` and `-` are evaluated from left to right, parentheses group.
+    // e.g. `(URO | ExtA) & gb2312 - big5`
+    pub fn eval(&self, expr: &str) -> Result<CharSet, CharSetError> {
+        fn parse(
+            sets: &CharSets,
+            expr: &str,
+            tokens: &[(usize, Token)],
+            pos: &mut usize,
+        ) -> Result<CharSet, CharSetError> {
+            let syntax_err = |p: usize| CharSetError::Syntax {
+                expr: expr.to_string(),
+                pos: p,
+            };
+            let operand = |pos: &mut usize| match tokens.get(*pos) {
+                Some((_, Token::Name(name))) => {
+                    *pos += 1;
+                    sets.get_set(name)
+                }
+                Some((_, Token::Open)) => {
+                    *pos += 1;
+                    let set = parse(sets, expr, tokens, pos)?;
+                    match tokens.get(*pos) {
+                        Some((_, Token::Close)) => {
+                            *pos += 1;
+                            Ok(set)
+                        }
+                        Some((p, _)) => Err(syntax_err(*p)),
+                        None => Err(syntax_err(expr.len())),
+                    }
+                }
+                Some((p, _)) => Err(syntax_err(*p)),
+                None => Err(syntax_err(expr.len())),
+            };
+
+            let mut set = operand(pos)?;
+            while let Some((_, Token::Op(op))) = tokens.get(*pos) {
+                *pos += 1;
+                let rhs = operand(pos)?;
+                set = match op {
+                    '|' => &set | &rhs,
+                    '&' => &set & &rhs,
+                    _ => &set - &rhs,
+                };
+            }
+            Ok(set)
+        }
+
+        let tokens = tokenize(expr);
+        let mut pos = 0;
+        let set = parse(self, expr, &tokens, &mut pos)?;
+        match tokens.get(pos) {
+            Some((p, _)) => Err(CharSetError::Syntax {
+                expr: expr.to_string(),
+                pos: *p,
+            }),
+            None => Ok(set),
+        }
+    }
+}
+
+enum Token {
+    Name(String),
+    Op(char),
+    Open,
+    Close,
+}
+
+fn is_name_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '|' | '&' | '-' | '(' | ')')
+}
+
+// A `-` between two name characters belongs to the name, so `gb2312-1 - big5` takes `big5`
+// from `gb2312-1`.
+fn tokenize(expr: &str) -> Vec<(usize, Token)> {
+    let mut tokens = vec![];
+    let mut name: Option<(usize, String)> = None;
+    let mut iter = expr.char_indices().peekable();
+    while let Some((i, c)) = iter.next() {
+        let token = match c {
+            '-' if name.is_some() && iter.peek().is_some_and(|(_, next)| is_name_char(*next)) => {
+                name.as_mut().unwrap().1.push(c);
+                continue;
+            }
+            '|' | '&' | '-' => Some(Token::Op(c)),
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            c if c.is_whitespace() => None,
+            c => {
+                name.get_or_insert_with(|| (i, String::new())).1.push(c);
+                continue;
+            }
+        };
+        if let Some((p, n)) = name.take() {
+            tokens.push((p, Token::Name(n)));
+        }
+        if let Some(token) = token {
+            tokens.push((i, token));
+        }
+    }
+    if let Some((p, n)) = name.take() {
+        tokens.push((p, Token::Name(n)));
+    }
+    tokens
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct Coverage {
+    pub total: usize,
+    pub generated: Vec<char>,
+    pub missing: Vec<char>,
+    pub failed: BTreeMap<char, String>,
+}
+
+impl Coverage {
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.generated.len() as f32 / self.total as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block() {
+        assert_eq!(UnicodeBlock::classify('永'), Some(UnicodeBlock::Uro));
+        assert_eq!(UnicodeBlock::classify('㐀'), Some(UnicodeBlock::ExtA));
+        assert_eq!(UnicodeBlock::classify('𠀀'), Some(UnicodeBlock::ExtB));
+        assert_eq!(UnicodeBlock::classify('a'), None);
+        assert_eq!(
+            UnicodeBlock::classify(char::from_u32(0x323B0).unwrap()),
+            Some(UnicodeBlock::ExtJ)
+        );
+        assert_eq!(CharSet::from_block(UnicodeBlock::Uro).len(), 0x5200);
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        let mut sets = CharSets::default();
+        sets.insert(
+            "a".to_string(),
+            CharSet::from_text("# comment 乙\n甲乙 丙\n丁"),
+        );
+        sets.insert("b".to_string(), CharSet::from_text("丙丁戊"));
+        assert_eq!(sets["a"].len(), 4);
+
+        let set = sets.eval("a & b").unwrap();
+        assert_eq!(set, CharSet::from_text("丙丁"));
+        let set = sets.eval("a - b | 戊").unwrap_err();
+        assert!(matches!(set, CharSetError::UnknownSet(name) if name == "戊"));
+        let set = sets.eval("a - (b & a)").unwrap();
+        assert_eq!(set, CharSet::from_text("甲乙"));
+        let set = sets.eval("(URO | ExtA) & b").unwrap();
+        assert_eq!(set, sets["b"]);
+
+        assert!(matches!(
+            sets.eval("a | (b"),
+            Err(CharSetError::Syntax { pos: 6, .. })
+        ));
+        assert!(matches!(
+            sets.eval("a b"),
+            Err(CharSetError::Syntax { pos: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_builtin() {
+        let mut sets = CharSets::default();
+        let gb2312 = [
+            sets.get_set("gb2312-1").unwrap(),
+            sets.get_set("gb2312-2").unwrap(),
+        ];
+        assert_eq!([gb2312[0].len(), gb2312[1].len()], [3755, 3008]);
+        assert!(gb2312[0].contains(&'啊') && gb2312[1].contains(&'亍'));
+        assert!(sets.get_set("gbk").unwrap().len() > 20000);
+        let big5 = sets.get_set("big5").unwrap();
+        assert!(big5.contains(&'國') && !big5.contains(&'国'));
+        assert!(sets.get_set("big5-1").unwrap().len() > 5000);
+        assert!(sets.get_set("gb18030-1").unwrap().len() > 27000);
+        assert!(sets.get_set("gb18030-3").unwrap().contains(&'𠀀'));
+
+        assert!(matches!(
+            sets.get_set("tgh"),
+            Err(CharSetError::UnknownSet(_))
+        ));
+        sets.insert("tgh".to_string(), CharSet::from_text("一𠀀"));
+        let level2 = sets.eval("tgh & ExtB | gb18030-1").unwrap();
+        assert!(level2.contains(&'𠀀') && !level2.contains(&'𠀁'));
+
+        // Hyphenated names.
+        assert_eq!(
+            CharSets::names_in("gb2312-1 -big5|(a-b)"),
+            ["gb2312-1", "big5", "a-b"]
+        );
+        let set = sets.eval("gb2312-1 - gb2312").unwrap();
+        assert!(set.is_empty());
+    }
+}
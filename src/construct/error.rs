@@ -13,6 +13,20 @@ pub enum CstError {
         tp: char,
         comp: String,
     },
+    // No assignment of the space satisfies the constraints.
+    Infeasible {
+        axis: crate::base::Axis,
+        msg: String,
+    },
+    // The config patch of a character override is invalid.
+    Override {
+        name: String,
+        msg: String,
+    },
+    // A saved `StrucComb` cannot be loaded.
+    Load {
+        msg: String,
+    },
 }
 
 impl fmt::Display for CstError {
@@ -31,6 +45,13 @@ impl fmt::Display for CstError {
             Self::Surround { tp, comp } => {
                 write!(f, "Components `{}` cannot be surrounded by {}", comp, tp)
             }
+            Self::Infeasible { axis, msg } => {
+                write!(f, "No space assignment in {:?}: {}", axis, msg)
+            }
+            Self::Override { name, msg } => {
+                write!(f, "Invalid config override of `{}`: {}", name, msg)
+            }
+            Self::Load { msg } => write!(f, "Invalid saved component: {}", msg),
         }
     }
 }
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+pub const LOCALE_SEPARATOR: char = '@';
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Debug)]
+pub enum Locale {
+    G,
+    T,
+    H,
+    J,
+    K,
+    V,
+}
+
+impl Locale {
+    pub fn list() -> [Locale; 6] {
+        [Self::G, Self::T, Self::H, Self::J, Self::K, Self::V]
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Self::G => 'G',
+            Self::T => 'T',
+            Self::H => 'H',
+            Self::J => 'J',
+            Self::K => 'K',
+            Self::V => 'V',
+        }
+    }
+
+    pub fn from_symbol(symbol: char) -> Option<Self> {
+        Self::list().into_iter().find(|l| l.symbol() == symbol)
+    }
+
+    // OpenType language system tag
+    pub fn language_tag(&self) -> &'static str {
+        match self {
+            Self::G => "ZHS",
+            Self::T => "ZHT",
+            Self::H => "ZHH",
+            Self::J => "JAN",
+            Self::K => "KOR",
+            Self::V => "VIT",
+        }
+    }
+
+    // `骨` -> `骨@J`
+    pub fn tagged_name(&self, name: &str) -> String {
+        format!("{name}{LOCALE_SEPARATOR}{}", self.symbol())
+    }
+
+    pub fn split_tagged(name: &str) -> Option<(&str, Locale)> {
+        let (base, tag) = name.rsplit_once(LOCALE_SEPARATOR)?;
+        let mut chars = tag.chars();
+        match (chars.next(), chars.next()) {
+            (Some(symbol), None) if !base.is_empty() => {
+                Self::from_symbol(symbol).map(|locale| (base, locale))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tagged_name() {
+        let name = Locale::J.tagged_name("骨");
+        assert_eq!(name, "骨@J");
+        assert_eq!(Locale::split_tagged(&name), Some(("骨", Locale::J)));
+        assert_eq!(Locale::split_tagged("骨"), None);
+        assert_eq!(Locale::split_tagged("骨@X"), None);
+        assert_eq!(Locale::split_tagged("@J"), None);
+    }
+}
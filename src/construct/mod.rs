@@ -6,3 +6,9 @@ pub use types::*;
 
 mod error;
 pub use error::*;
+
+mod charset;
+pub use charset::*;
+
+mod locale;
+pub use locale::*;
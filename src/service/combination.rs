@@ -1,31 +1,40 @@
-use super::Service;
 use super::algorithm as al;
-use super::space;
+use super::trace::{TraceEvent, Tracer};
+use super::{CharOverride, PatchedService, Service, solver};
 use crate::{
     base::*,
-    combination::{CompData, SharpnessModel, StrucComb, attrs},
+    combination::{CompData, StrucComb, attrs},
+    config,
     construct::{CharTree, Component, CpAttrs, CstError, CstType},
 };
 
 // start point
-pub fn get_char_tree(service: &impl Service, name: String) -> CharTree {
+pub fn get_char_tree(service: &impl Service, name: String, tracer: &mut Tracer) -> CharTree {
     get_char_tree_in(
         service,
         name,
         (CstType::Single, Section::Start),
         Default::default(),
+        tracer,
     )
 }
 
 pub fn get_comp_attrs<'a, 'b>(service: &'a impl Service, name: &'b str) -> Option<&'a CpAttrs> {
+    let supplement = &service.get_config().supplement;
+    let table = service.get_table();
     service
-        .get_config()
-        .supplement
-        .get(name)
-        .or(service.get_table().get(name))
+        .get_locale()
+        .map(|locale| locale.tagged_name(name))
+        .and_then(|tagged| supplement.get(&tagged).or(table.get(&tagged)))
+        .or_else(|| supplement.get(name).or(table.get(name)))
 }
 
-fn comb_remap(service: &impl Service, attrs: &mut CpAttrs, adjacency: DataHV<[bool; 2]>) {
+fn comb_remap(
+    service: &impl Service,
+    attrs: &mut CpAttrs,
+    adjacency: DataHV<[bool; 2]>,
+    tracer: &mut Tracer,
+) {
     // if let CstType::Scale(axis) = attrs.tp {
     // let mut idx = 0;
     // while idx != attrs.components.len() {
@@ -57,13 +66,17 @@ fn comb_remap(service: &impl Service, attrs: &mut CpAttrs, adjacency: DataHV<[bo
         let primary = &attrs.components[0];
         let mut p_attrs = match primary {
             Component::Char(p_name) => {
-                get_char_attrs_in(
-                    service,
-                    p_name.clone(),
-                    (attrs.tp, Section::Start),
-                    adjacency,
-                )
-                .1
+                tracer
+                    .within("0", |tracer| {
+                        get_char_attrs_in(
+                            service,
+                            p_name.clone(),
+                            (attrs.tp, Section::Start),
+                            adjacency,
+                            tracer,
+                        )
+                    })
+                    .1
             }
             Component::Complex(p_attrs) => p_attrs.clone(),
         };
@@ -125,10 +138,11 @@ fn get_char_attrs_in(
     name: String,
     in_tp: (CstType, Section),
     adjacency: DataHV<[bool; 2]>,
+    tracer: &mut Tracer,
 ) -> (String, CpAttrs) {
     let name = service
         .get_config()
-        .check_name_replace(&name, in_tp, adjacency)
+        .check_name_replace(&name, in_tp, adjacency, tracer)
         .unwrap_or(name);
     let attrs = get_comp_attrs(service, &name)
         .cloned()
@@ -142,9 +156,10 @@ fn get_char_tree_in(
     name: String,
     in_tp: (CstType, Section),
     adjacency: DataHV<[bool; 2]>,
+    tracer: &mut Tracer,
 ) -> CharTree {
-    let (name, attrs) = get_char_attrs_in(service, name, in_tp, adjacency);
-    get_tree_from_attrs(service, name, attrs, adjacency)
+    let (name, attrs) = get_char_attrs_in(service, name, in_tp, adjacency, tracer);
+    get_tree_from_attrs(service, name, attrs, adjacency, tracer)
 }
 
 fn get_tree_from_attrs(
@@ -152,18 +167,20 @@ fn get_tree_from_attrs(
     name: String,
     mut attrs: CpAttrs,
     adjacency: DataHV<[bool; 2]>,
+    tracer: &mut Tracer,
 ) -> CharTree {
-    comb_remap(service, &mut attrs, adjacency);
+    comb_remap(service, &mut attrs, adjacency, tracer);
     fn get_tree_from_comp(
         service: &impl Service,
         comp: Component,
         in_tp: (CstType, Section),
         adjacency: DataHV<[bool; 2]>,
+        tracer: &mut Tracer,
     ) -> CharTree {
         match comp {
-            Component::Char(c_name) => get_char_tree_in(service, c_name, in_tp, adjacency),
+            Component::Char(c_name) => get_char_tree_in(service, c_name, in_tp, adjacency, tracer),
             Component::Complex(c_attrs) => {
-                get_tree_from_attrs(service, c_attrs.comps_name(), c_attrs, adjacency)
+                get_tree_from_attrs(service, c_attrs.comps_name(), c_attrs, adjacency, tracer)
             }
         }
     }
@@ -189,7 +206,9 @@ fn get_tree_from_attrs(
                         n if n + 1 == end => Section::End,
                         _ => Section::Middle,
                     };
-                    get_tree_from_comp(service, c, (attrs.tp, in_tp), c_adjacency)
+                    tracer.within(&i.to_string(), |tracer| {
+                        get_tree_from_comp(service, c, (attrs.tp, in_tp), c_adjacency, tracer)
+                    })
                 })
                 .collect();
             CharTree {
@@ -212,18 +231,24 @@ fn get_tree_from_attrs(
                 }
             });
 
-            let sc = get_tree_from_comp(
-                service,
-                attrs.components.pop().unwrap(),
-                (attrs.tp, Section::End),
-                adjacency[1],
-            );
-            let pc = get_tree_from_comp(
-                service,
-                attrs.components.pop().unwrap(),
-                (attrs.tp, Section::Start),
-                adjacency[0],
-            );
+            let sc = tracer.within("1", |tracer| {
+                get_tree_from_comp(
+                    service,
+                    attrs.components.pop().unwrap(),
+                    (attrs.tp, Section::End),
+                    adjacency[1],
+                    tracer,
+                )
+            });
+            let pc = tracer.within("0", |tracer| {
+                get_tree_from_comp(
+                    service,
+                    attrs.components.pop().unwrap(),
+                    (attrs.tp, Section::Start),
+                    adjacency[0],
+                    tracer,
+                )
+            });
 
             CharTree {
                 name,
@@ -238,6 +263,7 @@ pub fn get_comb_proto_in(
     service: &impl Service,
     target: CharTree,
     adjacency: DataHV<[bool; 2]>,
+    tracer: &mut Tracer,
 ) -> Result<StrucComb, CstError> {
     match target.tp {
         CstType::Single => {
@@ -246,6 +272,16 @@ pub fn get_comb_proto_in(
                 .cloned()
                 .ok_or_else(|| CstError::Empty(target.name.clone()))?;
 
+            if let Some(ipa) = proto.attrs.get::<attrs::InPlaceAllocs>() {
+                tracer.record("", &target.name, || TraceEvent::InPlace {
+                    adjacency,
+                    rules: ipa
+                        .into_iter()
+                        .filter(|(rule, _)| config::place_match(rule, adjacency))
+                        .map(|(rule, _)| rule)
+                        .collect(),
+                });
+            }
             proto.set_allocs_in_adjacency(adjacency);
             Ok(StrucComb::new_single(target.name, proto))
         }
@@ -263,7 +299,9 @@ pub fn get_comb_proto_in(
                     c_in_place.hv_get_mut(axis)[1] = true;
                 }
 
-                combs.push(get_comb_proto_in(service, c_target, c_in_place)?);
+                combs.push(tracer.within(&i.to_string(), |tracer| {
+                    get_comb_proto_in(service, c_target, c_in_place, tracer)
+                })?);
             }
             Ok(StrucComb::new_complex(target.name, target.tp, combs))
         }
@@ -275,26 +313,142 @@ pub fn get_comb_proto_in(
     })
 }
 
+pub fn gen_struc_comb(
+    service: &impl Service,
+    target: CharTree,
+    tracer: &mut Tracer,
+) -> Result<StrucComb, CstError> {
+    let ovr = service.get_override(&target.name);
+    let patched = ovr
+        .map(|ovr| ovr.patched_config(service.get_config()))
+        .transpose()
+        .map_err(|msg| CstError::Override {
+            name: target.name.clone(),
+            msg,
+        })?
+        .flatten();
+    match patched {
+        Some(config) => gen_comb(
+            &PatchedService::with_config(service, config),
+            target,
+            ovr,
+            tracer,
+        ),
+        None => gen_comb(service, target, ovr, tracer),
+    }
+}
+
+pub fn gen_comb(
+    service: &impl Service,
+    target: CharTree,
+    ovr: Option<&CharOverride>,
+    tracer: &mut Tracer,
+) -> Result<StrucComb, CstError> {
+    let mut comb = get_comb_proto_in(service, target, Default::default(), tracer)?;
+    if let Some(ovr) = ovr {
+        ovr.apply_attrs(&mut comb);
+    }
+    let symmetry = service.get_config().get_symmetry();
+    match service.get_config().get_space_mode() {
+        config::SpaceMode::Greedy => {
+            let (assigns, _) = check_space(service, &mut comb, tracer)?;
+            assign_space(service, &mut comb, assigns, tracer);
+            if symmetry {
+                comb.symmetrize();
+            }
+            process_space(service, &mut comb, tracer);
+        }
+        config::SpaceMode::Solver => {
+            solve_space(service, &mut comb, tracer)?;
+            process_space(service, &mut comb, tracer);
+        }
+    }
+    if symmetry {
+        comb.symmetrize();
+    }
+    if tracer.is_enabled() {
+        for asym in comb.asymmetries() {
+            let name = comb
+                .node(&asym.path)
+                .map(|c| c.name.clone())
+                .unwrap_or_default();
+            tracer.record(&asym.path, &name, || TraceEvent::Asymmetry {
+                axis: asym.axis,
+                other: asym.other,
+            });
+        }
+    }
+    if let Some(ovr) = ovr {
+        ovr.apply_assigns(&mut comb);
+    }
+
+    Ok(comb)
+}
+
+// Paths, names and base lengths of the single components.
+fn leaf_lengths(comb: &StrucComb, axis: Axis) -> Vec<(String, String, usize)> {
+    fn leaves(comb: &StrucComb, path: String, axis: Axis, list: &mut Vec<(String, String, usize)>) {
+        match &comb.cdata {
+            CompData::Single { .. } => {
+                list.push((path, comb.name.clone(), comb.get_bases_length(axis, false)))
+            }
+            CompData::Scale { comps, .. } | CompData::Surround { comps, .. } => {
+                for (i, c) in comps.iter().enumerate() {
+                    leaves(c, StrucComb::child_path(&path, i), axis, list);
+                }
+            }
+        }
+    }
+
+    let mut list = vec![];
+    leaves(comb, String::new(), axis, &mut list);
+    list
+}
+
+fn reduce_space(comb: &mut StrucComb, axis: Axis, tracer: &mut Tracer) -> bool {
+    let before = tracer.is_enabled().then(|| leaf_lengths(comb, axis));
+    let ok = comb.reduce_space(axis, false);
+    if let (true, Some(before)) = (ok, before) {
+        for ((path, name, from), (.., to)) in before.into_iter().zip(leaf_lengths(comb, axis)) {
+            if from != to {
+                tracer.record(&path, &name, || TraceEvent::Reduce { axis, from, to });
+            }
+        }
+    }
+    ok
+}
+
 pub fn reduce_replace(
     service: &impl Service,
     comb: &mut StrucComb,
     axis: Axis,
+    tracer: &mut Tracer,
 ) -> Result<bool, CstError> {
-    fn replace(service: &impl Service, comb: &mut StrucComb, axis: Axis) -> Result<bool, CstError> {
+    fn replace(
+        service: &impl Service,
+        comb: &mut StrucComb,
+        axis: Axis,
+        tracer: &mut Tracer,
+    ) -> Result<bool, CstError> {
         let cfg = service.get_config();
         match cfg.reduce_replace_name(axis, &comb.name) {
             Some(new_name) => {
-                let new_tree = get_char_tree(service, new_name.to_string());
+                tracer.record("", &comb.name, || TraceEvent::Replace {
+                    axis,
+                    name: new_name.to_string(),
+                });
+                let new_tree = get_char_tree(service, new_name.to_string(), tracer);
                 let mut new_comb = get_comb_proto_in(
                     service,
                     new_tree,
                     comb.attrs.get::<attrs::Adjacencies>().unwrap(),
+                    tracer,
                 )?;
 
                 let old_len = comb.get_bases_length(axis, false);
                 loop {
-                    let new_len = *init_edges(service, &mut new_comb)?.hv_get(axis);
-                    if new_len <= old_len || !new_comb.reduce_space(axis, false) {
+                    let new_len = *init_edges(service, &mut new_comb, tracer)?.hv_get(axis);
+                    if new_len <= old_len || !reduce_space(&mut new_comb, axis, tracer) {
                         break;
                     }
                 }
@@ -306,7 +460,8 @@ pub fn reduce_replace(
                 {
                     loop {
                         let new_len = new_comb.get_bases_length(axis.inverse(), false);
-                        if new_len <= target || !new_comb.reduce_space(axis.inverse(), false) {
+                        if new_len <= target || !reduce_space(&mut new_comb, axis.inverse(), tracer)
+                        {
                             break;
                         }
                     }
@@ -320,7 +475,7 @@ pub fn reduce_replace(
     }
 
     match &mut comb.cdata {
-        CompData::Single { .. } => replace(service, comb, axis),
+        CompData::Single { .. } => replace(service, comb, axis, tracer),
         CompData::Scale {
             axis: c_axis,
             comps,
@@ -339,7 +494,9 @@ pub fn reduce_replace(
                 new_lengths.sort_by_key(|(_, c)| *c);
 
                 for (i, _) in new_lengths {
-                    if reduce_replace(service, &mut comps[i], axis)? {
+                    if tracer.within(&i.to_string(), |tracer| {
+                        reduce_replace(service, &mut comps[i], axis, tracer)
+                    })? {
                         ok = true;
                         break;
                     }
@@ -358,7 +515,9 @@ pub fn reduce_replace(
                     .collect();
 
                 for i in targets {
-                    ok |= reduce_replace(service, &mut comps[i], axis)?;
+                    ok |= tracer.within(&i.to_string(), |tracer| {
+                        reduce_replace(service, &mut comps[i], axis, tracer)
+                    })?;
                 }
             }
             Ok(ok)
@@ -372,6 +531,7 @@ fn init_edge_at_scale(
     comps: &mut Vec<StrucComb>,
     intervals: &mut Vec<usize>,
     axis: Axis,
+    tracer: &mut Tracer,
 ) -> Result<DataHV<usize>, CstError> {
     enum Record {
         None,
@@ -381,7 +541,14 @@ fn init_edge_at_scale(
         },
     }
 
-    fn flatten(comp: StrucComb, axis: Axis, records: &mut Vec<Record>) -> Vec<StrucComb> {
+    // `paths` collects the node paths of the flattened components.
+    fn flatten(
+        comp: StrucComb,
+        path: String,
+        axis: Axis,
+        records: &mut Vec<Record>,
+        paths: &mut Vec<String>,
+    ) -> Vec<StrucComb> {
         match comp.cdata {
             CompData::Scale {
                 axis: c_axis,
@@ -391,7 +558,11 @@ fn init_edge_at_scale(
                 let mut new_records = Vec::with_capacity(comps.len());
                 let list = comps
                     .into_iter()
-                    .flat_map(|c| flatten(c, axis, &mut new_records))
+                    .enumerate()
+                    .flat_map(|(i, c)| {
+                        let path = StrucComb::child_path(&path, i);
+                        flatten(c, path, axis, &mut new_records, paths)
+                    })
                     .collect();
                 records.push(Record::Name {
                     name: comp.name,
@@ -401,6 +572,7 @@ fn init_edge_at_scale(
             }
             _ => {
                 records.push(Record::None);
+                paths.push(path);
                 vec![comp]
             }
         }
@@ -437,9 +609,11 @@ fn init_edge_at_scale(
     }
 
     let mut records = Vec::with_capacity(comps.len());
+    let mut paths = Vec::with_capacity(comps.len());
     let mut new_comps: Vec<StrucComb> = comps
         .drain(..)
-        .flat_map(|c| flatten(c, axis, &mut records))
+        .enumerate()
+        .flat_map(|(i, c)| flatten(c, i.to_string(), axis, &mut records, &mut paths))
         .collect();
 
     let mut len_list: Vec<DataHV<usize>> = Vec::with_capacity(new_comps.len());
@@ -447,19 +621,21 @@ fn init_edge_at_scale(
 
     let mut new_intervals = loop {
         len_list.clear();
-        for c in new_comps.iter_mut() {
-            len_list.push(init_edges(service, c)?);
+        for (c, path) in new_comps.iter_mut().zip(paths.iter()) {
+            len_list.push(tracer.within(path, |tracer| init_edges(service, c, tracer))?);
         }
 
         cfg.set_main_comp_axis(
             &mut new_comps,
+            &paths,
             axis.inverse(),
             &len_list
                 .iter()
                 .map(|list| *list.hv_get(axis.inverse()))
                 .collect(),
+            tracer,
         );
-        if let Some(new_intervals) = cfg.set_intervals_axis(&mut new_comps, axis) {
+        if let Some(new_intervals) = cfg.set_intervals_axis(&mut new_comps, &paths, axis, tracer) {
             break new_intervals;
         }
     };
@@ -487,7 +663,11 @@ fn init_edge_at_scale(
     Ok(size)
 }
 
-fn init_edges(service: &impl Service, comb: &mut StrucComb) -> Result<DataHV<usize>, CstError> {
+fn init_edges(
+    service: &impl Service,
+    comb: &mut StrucComb,
+    tracer: &mut Tracer,
+) -> Result<DataHV<usize>, CstError> {
     comb.blanks = Default::default();
 
     let l = match &mut comb.cdata {
@@ -503,7 +683,9 @@ fn init_edges(service: &impl Service, comb: &mut StrucComb) -> Result<DataHV<usi
             comps,
             intervals,
             ..
-        } => Ok(init_edge_at_scale(service, comps, intervals, *c_axis)?),
+        } => Ok(init_edge_at_scale(
+            service, comps, intervals, *c_axis, tracer,
+        )?),
         CompData::Surround { .. } => todo!(), // surround
     };
 
@@ -520,6 +702,7 @@ fn init_edges(service: &impl Service, comb: &mut StrucComb) -> Result<DataHV<usi
 pub fn check_space(
     service: &impl Service,
     comb: &mut StrucComb,
+    tracer: &mut Tracer,
 ) -> Result<(DataHV<f32>, DataHV<usize>), CstError> {
     let cfg = service.get_config();
     let zishen = cfg
@@ -531,7 +714,7 @@ pub fn check_space(
     let mut white_area: DataHV<[f32; 2]> = DataHV::default();
     let mut levels: DataHV<usize> = DataHV::default();
 
-    let mut base_lens = init_edges(service, comb)?;
+    let mut base_lens = init_edges(service, comb, tracer)?;
     let mut check_state: Vec<Axis> = Axis::list().into();
     let mut first = true;
 
@@ -543,6 +726,7 @@ pub fn check_space(
         let reduce_trigger = cfg.get_reduce_trigger(axis);
         let replace_trigger = cfg.get_replace_trigger(axis);
         let vc_val = cfg.get_visual_corr(axis);
+        let sharpness = cfg.get_sharpness(axis);
 
         loop {
             let base_len = *base_lens.hv_get(axis);
@@ -550,10 +734,7 @@ pub fn check_space(
 
             let white = (zishen - length) / 2.0;
             let vcorr = Side::fb().map(|side| {
-                let vcorr = ((1.0
-                    - comb
-                        .get_edge(axis, side, false)
-                        .sharpness(SharpnessModel::ZeroOne))
+                let vcorr = ((1.0 - comb.get_edge(axis, side, false).sharpness(sharpness))
                     * vc_val)
                     .min(white - min_white);
                 length += vcorr;
@@ -568,18 +749,18 @@ pub fn check_space(
             match r {
                 Some(level) => {
                     let scale = length / base_len as f32;
-                    if scale < reduce_trigger && comb.reduce_space(axis, false) {
+                    if scale < reduce_trigger && reduce_space(comb, axis, tracer) {
                         if check_state.len() == 1 {
                             check_state.push(axis.inverse());
                         }
-                        base_lens = init_edges(service, comb)?;
+                        base_lens = init_edges(service, comb, tracer)?;
                         continue;
                     }
-                    if scale < replace_trigger && reduce_replace(service, comb, axis)? {
+                    if scale < replace_trigger && reduce_replace(service, comb, axis, tracer)? {
                         if check_state.len() == 1 {
                             check_state.push(axis.inverse());
                         }
-                        base_lens = init_edges(service, comb)?;
+                        base_lens = init_edges(service, comb, tracer)?;
                         continue;
                     }
 
@@ -592,16 +773,32 @@ pub fn check_space(
 
                     *assign.hv_get_mut(axis) = length;
                     *levels.hv_get_mut(axis) = level;
+                    tracer.record("", &comb.name, || TraceEvent::Level {
+                        axis,
+                        level,
+                        base_len,
+                        length,
+                    });
+                    for side in Side::fb() {
+                        tracer.record("", &comb.name, || TraceEvent::VisualCorr {
+                            axis,
+                            side,
+                            val: vcorr[side.n()],
+                        });
+                    }
                     break;
                 }
                 None => {
-                    if comb.reduce_space(axis, false) || reduce_replace(service, comb, axis)? {
+                    if reduce_space(comb, axis, tracer)
+                        || reduce_replace(service, comb, axis, tracer)?
+                    {
                         if check_state.len() == 1 {
                             check_state.push(axis.inverse());
                         }
-                        base_lens = init_edges(service, comb)?;
+                        base_lens = init_edges(service, comb, tracer)?;
                         continue;
                     } else if first {
+                        tracer.record("", &comb.name, || TraceEvent::AxisSwap { axis });
                         check_state.swap(0, 1);
                         first = false;
                         continue;
@@ -623,7 +820,56 @@ pub fn check_space(
     Ok((assign, levels))
 }
 
-pub fn assign_space(service: &impl Service, comb: &mut StrucComb, mut assigns: DataHV<f32>) {
+// Replaces `check_space` and `assign_space`: the space of both axes is solved jointly, reducing
+// the combination while it does not fit.
+pub fn solve_space(
+    service: &impl Service,
+    comb: &mut StrucComb,
+    tracer: &mut Tracer,
+) -> Result<(), CstError> {
+    init_edges(service, comb, tracer)?;
+    'solve: loop {
+        let mut spaces = Vec::with_capacity(2);
+        for axis in Axis::list() {
+            match solver::solve_axis(service, comb, axis) {
+                Ok(space) => spaces.push(space),
+                Err(e) => {
+                    if reduce_space(comb, axis, tracer)
+                        || reduce_replace(service, comb, axis, tracer)?
+                    {
+                        init_edges(service, comb, tracer)?;
+                        continue 'solve;
+                    }
+                    return Err(CstError::Infeasible {
+                        axis,
+                        msg: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut white_area = DataHV::default();
+        for space in spaces {
+            let axis = space.axis;
+            tracer.record("", &comb.name, || TraceEvent::Level {
+                axis,
+                level: space.level,
+                base_len: space.base_len,
+                length: space.length,
+            });
+            *white_area.hv_get_mut(axis) = space.apply(comb);
+        }
+        comb.set_white_area(&white_area);
+        return Ok(());
+    }
+}
+
+pub fn assign_space(
+    service: &impl Service,
+    comb: &mut StrucComb,
+    mut assigns: DataHV<f32>,
+    tracer: &mut Tracer,
+) {
     let cfg_units = &service.get_config().units;
     let mut levels = DataHV::splat(0);
     let mut units = DataHV::splat(0.0);
@@ -634,6 +880,7 @@ pub fn assign_space(service: &impl Service, comb: &mut StrucComb, mut assigns: D
         if base_len != 0.0 {
             let white_cfg = space_setting.0.hv_get(axis);
             let visual_corr = space_setting.1.hv_get(axis);
+            let sharpness = service.get_config().get_sharpness(axis);
 
             let assign = assigns.hv_get_mut(axis);
 
@@ -651,6 +898,7 @@ pub fn assign_space(service: &impl Service, comb: &mut StrucComb, mut assigns: D
             let white_unit = space_setting.2.hv_get(axis).unwrap_or(unit).min(unit);
             *levels.hv_get_mut(axis) = level;
             *units.hv_get_mut(axis) = unit;
+            tracer.record("", &comb.name, || TraceEvent::Assign { axis, level, unit });
 
             let mut assigns_val = vec![
                 AssignVal::from_base(white_base[0] * scale, white_unit * white_base[0]),
@@ -667,10 +915,7 @@ pub fn assign_space(service: &impl Service, comb: &mut StrucComb, mut assigns: D
             if base_len.abs() > 0.5 {
                 // No zero
                 Side::fb().into_iter().for_each(|side| {
-                    let vcorr = (1.0
-                        - comb
-                            .get_edge(axis, side, false)
-                            .sharpness(SharpnessModel::ZeroOne))
+                    let vcorr = (1.0 - comb.get_edge(axis, side, false).sharpness(sharpness))
                         * visual_corr[side.n()];
                     if vcorr < assigns_val[[0, 2][side.n()]].excess {
                         assigns_val[1].excess += vcorr;
@@ -759,24 +1004,27 @@ pub fn assign_space(service: &impl Service, comb: &mut StrucComb, mut assigns: D
             } else {
                 0.0
             };
-            for (c, l) in comps.iter_mut().zip(lengths) {
+            for (i, (c, l)) in comps.iter_mut().zip(lengths).enumerate() {
                 let mut new_assigns = assigns.clone();
                 *new_assigns.hv_get_mut(*c_axis) = scale * l as f32;
-                assign_space(service, c, new_assigns);
+                tracer.within(&i.to_string(), |tracer| {
+                    assign_space(service, c, new_assigns, tracer)
+                });
             }
         }
         CompData::Surround { .. } => todo!(), // surround
     }
 }
 
-pub fn process_space(service: &impl Service, comb: &mut StrucComb) {
+pub fn process_space(service: &impl Service, comb: &mut StrucComb, tracer: &mut Tracer) {
     let cfg = service.get_config();
     if let (order, Some(setting)) = cfg.get_space_ctrls() {
         for ctrl in order {
-            match (ctrl, setting.get(ctrl)) {
-                ("subarea", Some(v)) => space::ctrl_subarea(comb, v),
-                ("trend", Some(v)) => space::ctrl_trend(comb, v),
-                ("subcomp", Some(v)) => space::ctrl_subcomp(comb, v),
+            tracer.record("", &comb.name, || TraceEvent::SpaceCtrl {
+                name: ctrl.to_string(),
+            });
+            match (service.get_space_ctrl(ctrl), setting.get(ctrl)) {
+                (Some(stage), Some(v)) => stage.process(comb, v, tracer),
                 _ => log::warn!("Incorrect space control label: {ctrl}"),
             }
         }
@@ -0,0 +1,142 @@
+use super::{space, trace::Tracer};
+use crate::combination::StrucComb;
+
+use serde_json as sj;
+use std::collections::BTreeMap;
+
+// A stage of `process_space`, named in `space_ctrls.order` and given the settings under that name.
+pub trait SpaceCtrl {
+    fn process(&self, comb: &mut StrucComb, settings: &sj::Value, tracer: &mut Tracer);
+}
+
+impl<F> SpaceCtrl for F
+where
+    F: Fn(&mut StrucComb, &sj::Value),
+{
+    fn process(&self, comb: &mut StrucComb, settings: &sj::Value, _tracer: &mut Tracer) {
+        self(comb, settings)
+    }
+}
+
+pub struct Subarea;
+
+impl SpaceCtrl for Subarea {
+    fn process(&self, comb: &mut StrucComb, settings: &sj::Value, _tracer: &mut Tracer) {
+        space::ctrl_subarea(comb, settings)
+    }
+}
+
+pub struct Trend;
+
+impl SpaceCtrl for Trend {
+    fn process(&self, comb: &mut StrucComb, settings: &sj::Value, tracer: &mut Tracer) {
+        space::ctrl_trend(comb, settings, tracer)
+    }
+}
+
+pub struct Subcomp;
+
+impl SpaceCtrl for Subcomp {
+    fn process(&self, comb: &mut StrucComb, settings: &sj::Value, _tracer: &mut Tracer) {
+        space::ctrl_subcomp(comb, settings)
+    }
+}
+
+pub struct Gray;
+
+impl SpaceCtrl for Gray {
+    fn process(&self, comb: &mut StrucComb, settings: &sj::Value, _tracer: &mut Tracer) {
+        space::ctrl_gray(comb, settings)
+    }
+}
+
+pub struct Align;
+
+impl SpaceCtrl for Align {
+    fn process(&self, comb: &mut StrucComb, settings: &sj::Value, _tracer: &mut Tracer) {
+        space::ctrl_align(comb, settings)
+    }
+}
+
+pub fn builtin(name: &str) -> Option<&'static dyn SpaceCtrl> {
+    match name {
+        "subarea" => Some(&Subarea),
+        "trend" => Some(&Trend),
+        "subcomp" => Some(&Subcomp),
+        "gray" => Some(&Gray),
+        "align" => Some(&Align),
+        _ => None,
+    }
+}
+
+// Custom space controls, which take precedence over the builtin ones of the same name.
+#[derive(Default)]
+pub struct CtrlRegistry {
+    ctrls: BTreeMap<String, Box<dyn SpaceCtrl>>,
+}
+
+impl CtrlRegistry {
+    pub fn register(&mut self, name: impl Into<String>, ctrl: impl SpaceCtrl + 'static) {
+        self.ctrls.insert(name.into(), Box::new(ctrl));
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.ctrls.remove(name).is_some()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.ctrls.keys().map(|name| name.as_str()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn SpaceCtrl> {
+        self.ctrls
+            .get(name)
+            .map(|ctrl| ctrl.as_ref())
+            .or_else(|| builtin(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::*,
+        combination::StrucProto,
+        construct::{CharTree, CstTable},
+        service::{Service, SimpleService},
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn test_custom_ctrl() {
+        let mut service = SimpleService::new(CstTable::empty());
+        service.strucs.insert(
+            "一".to_string(),
+            StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]),
+        );
+        service.config = sj::from_value(sj::json!({
+            "space_ctrls": { "order": ["subarea", "balance"], "subarea": {}, "balance": { "k": 1 } }
+        }))
+        .unwrap();
+
+        let calls = Rc::new(RefCell::new(vec![]));
+        let log = calls.clone();
+        service.ctrls.register(
+            "balance",
+            move |comb: &mut StrucComb, settings: &sj::Value| {
+                log.borrow_mut().push((comb.name.clone(), settings.clone()));
+            },
+        );
+        assert_eq!(service.ctrls.names(), vec!["balance"]);
+        assert!(service.get_space_ctrl("trend").is_some());
+        assert!(service.get_space_ctrl("unknown").is_none());
+
+        service
+            .get_struc_comb(CharTree::new_single("一".to_string()))
+            .unwrap();
+        assert_eq!(
+            *calls.borrow(),
+            vec![("一".to_string(), sj::json!({ "k": 1 }))]
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use super::Service;
+use crate::{
+    base::*,
+    combination::{
+        CompTree, StrucComb,
+        comb::{CompInfoData, StrucCombInfo},
+    },
+    config::SmoothSetting,
+};
+
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Write};
+
+pub const OLD_COLOR: &str = "#d62728";
+pub const NEW_COLOR: &str = "#1f77b4";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum NodeChange {
+    // The component was replaced by another of a different name.
+    Replaced {
+        old: String,
+        new: String,
+    },
+    // The combination type or the number of children changed, like `⿰2` to `⿱2`.
+    Structure {
+        old: String,
+        new: String,
+    },
+    PathCount {
+        old: usize,
+        new: usize,
+    },
+    // The number of key points of the path `path` changed.
+    PathShape {
+        path: usize,
+        old: usize,
+        new: usize,
+    },
+    Moved {
+        path: usize,
+        point: usize,
+        old: WorkPoint,
+        new: WorkPoint,
+    },
+    Blanks {
+        axis: Axis,
+        old: [f32; 2],
+        new: [f32; 2],
+    },
+    Assigns {
+        axis: Axis,
+        old: Vec<f32>,
+        new: Vec<f32>,
+    },
+    Intervals {
+        old: Vec<f32>,
+        new: Vec<f32>,
+    },
+}
+
+// `path` is the path of the node as in `StrucComb::node`, `name` its old name.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct NodeDiff {
+    pub path: String,
+    pub name: String,
+    pub changes: Vec<NodeChange>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum CharDiff {
+    Changed(Vec<NodeDiff>),
+    // Generating failed in at least one of the runs.
+    Failed {
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+fn push_changes(list: &mut Vec<NodeDiff>, path: &str, name: &str, changes: Vec<NodeChange>) {
+    if changes.is_empty() {
+        return;
+    }
+    match list.iter_mut().find(|node| node.path == path) {
+        Some(node) => node.changes.extend(changes),
+        None => list.push(NodeDiff {
+            path: path.to_string(),
+            name: name.to_string(),
+            changes,
+        }),
+    }
+}
+
+fn values_changed(old: &[f32], new: &[f32], tolerance: f32) -> bool {
+    old.len() != new.len() || old.iter().zip(new).any(|(a, b)| (a - b).abs() > tolerance)
+}
+
+fn structure(tree: &CompTree) -> String {
+    format!("{}{}", tree.tp.symbol(), tree.children.len())
+}
+
+fn diff_tree_in(
+    old: &CompTree,
+    new: &CompTree,
+    path: &str,
+    tolerance: f32,
+    list: &mut Vec<NodeDiff>,
+) {
+    let mut changes = vec![];
+    if old.name != new.name {
+        changes.push(NodeChange::Replaced {
+            old: old.name.clone(),
+            new: new.name.clone(),
+        });
+    }
+
+    let same_structure = old.tp == new.tp && old.children.len() == new.children.len();
+    if !same_structure {
+        changes.push(NodeChange::Structure {
+            old: structure(old),
+            new: structure(new),
+        });
+    } else if old.paths.len() != new.paths.len() {
+        changes.push(NodeChange::PathCount {
+            old: old.paths.len(),
+            new: new.paths.len(),
+        });
+    } else {
+        for (i, (p1, p2)) in old.paths.iter().zip(&new.paths).enumerate() {
+            if p1.len() != p2.len() {
+                changes.push(NodeChange::PathShape {
+                    path: i,
+                    old: p1.len(),
+                    new: p2.len(),
+                });
+                continue;
+            }
+            for (j, (kp1, kp2)) in p1.iter().zip(p2).enumerate() {
+                if (kp1.pos - kp2.pos).length() > tolerance {
+                    changes.push(NodeChange::Moved {
+                        path: i,
+                        point: j,
+                        old: kp1.pos,
+                        new: kp2.pos,
+                    });
+                }
+            }
+        }
+    }
+    push_changes(list, path, &old.name, changes);
+
+    if same_structure {
+        for (i, (c1, c2)) in old.children.iter().zip(&new.children).enumerate() {
+            diff_tree_in(c1, c2, &StrucComb::child_path(path, i), tolerance, list);
+        }
+    }
+}
+
+fn diff_info_in(
+    old: &StrucCombInfo,
+    new: &StrucCombInfo,
+    path: &str,
+    tolerance: f32,
+    list: &mut Vec<NodeDiff>,
+) {
+    let mut changes = vec![];
+    for axis in Axis::list() {
+        let (b1, b2) = (*old.blanks.hv_get(axis), *new.blanks.hv_get(axis));
+        if values_changed(&b1, &b2, tolerance) {
+            changes.push(NodeChange::Blanks {
+                axis,
+                old: b1,
+                new: b2,
+            });
+        }
+    }
+
+    match (&old.cdata, &new.cdata) {
+        (CompInfoData::Single { assigns: a1, .. }, CompInfoData::Single { assigns: a2, .. }) => {
+            for axis in Axis::list() {
+                let (a1, a2) = (a1.hv_get(axis), a2.hv_get(axis));
+                if values_changed(a1, a2, tolerance) {
+                    changes.push(NodeChange::Assigns {
+                        axis,
+                        old: a1.clone(),
+                        new: a2.clone(),
+                    });
+                }
+            }
+        }
+        (
+            CompInfoData::Scale {
+                comps: c1,
+                intervals_val: i1,
+                ..
+            },
+            CompInfoData::Scale {
+                comps: c2,
+                intervals_val: i2,
+                ..
+            },
+        ) => {
+            if values_changed(i1, i2, tolerance) {
+                changes.push(NodeChange::Intervals {
+                    old: i1.clone(),
+                    new: i2.clone(),
+                });
+            }
+            if c1.len() == c2.len() {
+                push_changes(list, path, &old.name, std::mem::take(&mut changes));
+                for (i, (c1, c2)) in c1.iter().zip(c2).enumerate() {
+                    diff_info_in(c1, c2, &StrucComb::child_path(path, i), tolerance, list);
+                }
+            }
+        }
+        _ => {}
+    }
+    push_changes(list, path, &old.name, changes);
+}
+
+// Key points moving no more than `tolerance` are unchanged.
+pub fn diff_trees(old: &CompTree, new: &CompTree, tolerance: f32) -> Vec<NodeDiff> {
+    let mut list = vec![];
+    diff_tree_in(old, new, "", tolerance, &mut list);
+    list
+}
+
+pub fn diff_infos(old: &StrucCombInfo, new: &StrucCombInfo, tolerance: f32) -> Vec<NodeDiff> {
+    let mut list = vec![];
+    diff_info_in(old, new, "", tolerance, &mut list);
+    list
+}
+
+// Both the geometry and the assigned values, merged per node. The paths of each side are smoothed
+// with its setting in `smooth`.
+pub fn diff_combs(
+    old: &StrucComb,
+    new: &StrucComb,
+    smooth: [Option<&SmoothSetting>; 2],
+    tolerance: f32,
+) -> Vec<NodeDiff> {
+    let mut list = diff_trees(
+        &old.get_comp_tree(smooth[0]),
+        &new.get_comp_tree(smooth[1]),
+        tolerance,
+    );
+    for node in diff_infos(&old.get_comb_info(), &new.get_comb_info(), tolerance) {
+        push_changes(&mut list, &node.path, &node.name, node.changes);
+    }
+    list
+}
+
+// Only the characters that changed are listed.
+pub fn diff_services(
+    old: &impl Service,
+    new: &impl Service,
+    chars: &[char],
+    tolerance: f32,
+) -> BTreeMap<char, CharDiff> {
+    old.gen_chars(chars)
+        .into_iter()
+        .zip(new.gen_chars(chars))
+        .filter_map(|((chr, r1), (_, r2))| {
+            let diff = match (r1, r2) {
+                (Ok(c1), Ok(c2)) => {
+                    let smooth = [old.get_config().get_smooth(), new.get_config().get_smooth()];
+                    let nodes = diff_combs(&c1, &c2, smooth, tolerance);
+                    match nodes.is_empty() {
+                        true => None,
+                        false => Some(CharDiff::Changed(nodes)),
+                    }
+                }
+                (Err(e1), Err(e2)) if e1.to_string() == e2.to_string() => None,
+                (r1, r2) => Some(CharDiff::Failed {
+                    old: r1.err().map(|e| e.to_string()),
+                    new: r2.err().map(|e| e.to_string()),
+                }),
+            };
+            diff.map(|diff| (chr, diff))
+        })
+        .collect()
+}
+
+fn tree_paths<'a>(tree: &'a CompTree, list: &mut Vec<&'a Vec<WorkKeyPoint>>) {
+    list.extend(&tree.paths);
+    tree.children.iter().for_each(|c| tree_paths(c, list));
+}
+
+// The old paths in `OLD_COLOR` under the new ones in `NEW_COLOR`.
+pub fn overlay_svg(old: &CompTree, new: &CompTree) -> String {
+    let (mut old_paths, mut new_paths) = (vec![], vec![]);
+    tree_paths(old, &mut old_paths);
+    tree_paths(new, &mut new_paths);
+
+    let mut bounds = WorkBox::new(WorkPoint::zero(), WorkPoint::splat(1.0));
+    old_paths
+        .iter()
+        .chain(&new_paths)
+        .flat_map(|path| path.iter())
+        .for_each(|kp| {
+            bounds.min = bounds.min.min(kp.pos);
+            bounds.max = bounds.max.max(kp.pos);
+        });
+    let size = bounds.size();
+    let stroke = size.width.max(size.height) * 0.01;
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        bounds.min.x - stroke * 2.0,
+        bounds.min.y - stroke * 2.0,
+        size.width + stroke * 4.0,
+        size.height + stroke * 4.0
+    );
+    for (id, color, paths) in [("old", OLD_COLOR, old_paths), ("new", NEW_COLOR, new_paths)] {
+        write!(
+            svg,
+            r#"<g id="{id}" fill="none" stroke="{color}" stroke-width="{stroke}" stroke-opacity="0.7">"#
+        )
+        .unwrap();
+        for path in paths {
+            let mut d: Vec<String> = vec![];
+            let mut ctrls = vec![];
+            for kp in path.iter().filter(|kp| !kp.is_mark()) {
+                let pos = format!("{} {}", kp.pos.x, kp.pos.y);
+                if kp.is_off_curve() {
+                    ctrls.push(pos);
+                    continue;
+                }
+                d.push(match (d.is_empty(), ctrls.as_slice()) {
+                    (true, _) => format!("M{pos}"),
+                    (false, [c]) => format!("Q{c} {pos}"),
+                    (false, [c1, c2]) => format!("C{c1} {c2} {pos}"),
+                    _ => format!("L{pos}"),
+                });
+                ctrls.clear();
+            }
+            if d.len() > 1 {
+                write!(svg, r#"<path d="{}"/>"#, d.join(" ")).unwrap();
+            }
+        }
+        svg.push_str("</g>");
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        construct::CstType,
+        service::test_util::{leaf, stacked_service},
+    };
+    use serde_json as sj;
+
+    #[test]
+    fn test_diff_trees() {
+        let tree = |children| CompTree {
+            name: "x".to_string(),
+            tp: CstType::Scale(Axis::Horizontal),
+            paths: vec![],
+            children,
+            alloc_box: WorkBox::zero(),
+            ink_box: None,
+            blanks: Default::default(),
+            metrics: None,
+        };
+        let old = tree(vec![
+            leaf("a", vec![vec![(0.1, 0.5), (0.4, 0.5)]]),
+            leaf("b", vec![vec![(0.6, 0.5), (0.9, 0.5)]]),
+        ]);
+        let new = tree(vec![
+            leaf("a", vec![vec![(0.1, 0.5), (0.45, 0.5)]]),
+            leaf("c", vec![vec![(0.6, 0.5), (0.9, 0.5), (0.9, 0.9)]]),
+        ]);
+
+        let diff = diff_trees(&old, &new, 1e-4);
+        assert_eq!(
+            diff,
+            vec![
+                NodeDiff {
+                    path: "0".to_string(),
+                    name: "a".to_string(),
+                    changes: vec![NodeChange::Moved {
+                        path: 0,
+                        point: 1,
+                        old: WorkPoint::new(0.4, 0.5),
+                        new: WorkPoint::new(0.45, 0.5),
+                    }],
+                },
+                NodeDiff {
+                    path: "1".to_string(),
+                    name: "b".to_string(),
+                    changes: vec![
+                        NodeChange::Replaced {
+                            old: "b".to_string(),
+                            new: "c".to_string(),
+                        },
+                        NodeChange::PathShape {
+                            path: 0,
+                            old: 2,
+                            new: 3,
+                        },
+                    ],
+                },
+            ]
+        );
+        assert!(diff_trees(&old, &old, 0.0).is_empty());
+
+        let diff = diff_trees(&old, &leaf("x", vec![vec![(0.0, 0.0), (1.0, 1.0)]]), 1e-4);
+        assert_eq!(
+            diff[0].changes,
+            vec![NodeChange::Structure {
+                old: "⿰2".to_string(),
+                new: "□0".to_string(),
+            }]
+        );
+
+        let svg = overlay_svg(&old, &new);
+        assert_eq!(svg.matches("<path").count(), 4);
+        assert!(svg.contains(OLD_COLOR) && svg.contains(NEW_COLOR));
+        assert!(svg.contains(r#"d="M0.6 0.5 L0.9 0.5 L0.9 0.9""#));
+    }
+
+    #[test]
+    fn test_diff_services() {
+        let old = stacked_service();
+        let mut new = stacked_service();
+        new.config = sj::from_value(sj::json!({ "zimian": [[0, 0.8]] })).unwrap();
+
+        assert!(diff_services(&old, &old, &['一', '二'], 1e-4).is_empty());
+        let diff = diff_services(&old, &new, &['一', '二', '三'], 1e-4);
+        assert_eq!(diff.keys().collect::<String>(), "一二");
+        let CharDiff::Changed(nodes) = &diff[&'一'] else {
+            panic!("{diff:?}");
+        };
+        assert_eq!(nodes[0].path, "");
+        assert!(
+            nodes[0]
+                .changes
+                .iter()
+                .any(|c| matches!(c, NodeChange::Moved { .. }))
+        );
+        assert!(nodes[0].changes.iter().any(|c| matches!(
+            c,
+            NodeChange::Assigns {
+                axis: Axis::Horizontal,
+                ..
+            }
+        )));
+    }
+}
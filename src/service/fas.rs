@@ -1,8 +1,16 @@
+use super::{
+    CharOverride,
+    migration::{self, MigrationReport},
+};
 use crate::{combination::StrucProto, config::Config};
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use serde_json as sj;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct Strucs(BTreeMap<String, StrucProto>);
@@ -38,13 +46,39 @@ impl Strucs {
 pub struct FasFile {
     pub name: String,
     pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
     pub strucs: Strucs,
     pub config: Config,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, CharOverride>,
 }
 
 impl FasFile {
     pub fn from_file(path: &str) -> Result<Self> {
-        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+        FasChain::load(path)?.resolve()
+    }
+
+    pub fn from_value(mut value: sj::Value) -> Result<(Self, MigrationReport)> {
+        let report = migration::migrate(&mut value)?;
+        Ok((sj::from_value(value)?, report))
+    }
+
+    // Only keeps what differs from `base`, the merged data of the base chain.
+    pub fn to_layer(&self, base: &sj::Value) -> Result<sj::Value> {
+        let full = sj::to_value(self)?;
+        let mut layer = diff_value(base, &full).unwrap_or(sj::json!({}));
+        for key in ["name", "version", "base"] {
+            if let Some(val) = full.get(key) {
+                layer[key] = val.clone();
+            }
+        }
+        Ok(layer)
+    }
+
+    pub fn save_layer(&self, path: &str, base: &sj::Value) -> Result<usize> {
+        let texts = sj::to_string_pretty(&self.to_layer(base)?)?;
+        Ok(std::fs::write(path, &texts).map(|_| texts.len())?)
     }
 
     pub fn save(&self, path: &str) -> Result<usize> {
@@ -58,14 +92,7 @@ impl FasFile {
     }
 
     pub fn versions(&self) -> [u32; 2] {
-        let mut iter = self
-            .version
-            .split('.')
-            .map(|n| n.parse::<u32>().unwrap_or_default());
-        [
-            iter.next().unwrap_or_default(),
-            iter.next().unwrap_or_default(),
-        ]
+        migration::parse_version(&self.version).unwrap_or_default()
     }
 }
 
@@ -73,13 +100,131 @@ impl std::default::Default for FasFile {
     fn default() -> Self {
         Self {
             name: "untile".to_string(),
-            version: "0.1".to_string(),
+            version: migration::version_str(migration::CURRENT_VERSION),
+            base: None,
             strucs: Default::default(),
             config: Default::default(),
+            overrides: Default::default(),
         }
     }
 }
 
+// Objects are merged recursively, `null` removes a key and other values are replaced.
+pub fn merge_value(target: &mut sj::Value, layer: sj::Value) {
+    match (target, layer) {
+        (sj::Value::Object(target), sj::Value::Object(layer)) => {
+            for (key, val) in layer {
+                if val.is_null() {
+                    target.remove(&key);
+                } else {
+                    match target.get_mut(&key) {
+                        Some(t) => merge_value(t, val),
+                        None => {
+                            target.insert(key, val);
+                        }
+                    }
+                }
+            }
+        }
+        (target, layer) => *target = layer,
+    }
+}
+
+// The inverse of `merge_value`: merging the result onto `base` gives `target`.
+pub fn diff_value(base: &sj::Value, target: &sj::Value) -> Option<sj::Value> {
+    match (base, target) {
+        (sj::Value::Object(base), sj::Value::Object(target)) => {
+            let mut diff = sj::value::Map::new();
+            for (key, val) in target {
+                match base.get(key) {
+                    Some(b) => {
+                        if let Some(d) = diff_value(b, val) {
+                            diff.insert(key.clone(), d);
+                        }
+                    }
+                    None => {
+                        diff.insert(key.clone(), val.clone());
+                    }
+                }
+            }
+            base.keys()
+                .filter(|key| !target.contains_key(*key))
+                .for_each(|key| {
+                    diff.insert(key.clone(), sj::Value::Null);
+                });
+
+            match diff.is_empty() {
+                true => None,
+                false => Some(sj::Value::Object(diff)),
+            }
+        }
+        (base, target) => match base == target {
+            true => None,
+            false => Some(target.clone()),
+        },
+    }
+}
+
+// The layers of a file, from the root base to the file itself.
+pub struct FasChain {
+    pub layers: Vec<(PathBuf, sj::Value, MigrationReport)>,
+}
+
+impl FasChain {
+    pub fn load(path: &str) -> Result<Self> {
+        let mut layers = vec![];
+        let mut path = PathBuf::from(path);
+        loop {
+            let canonical = path.canonicalize()?;
+            if layers.iter().any(|(p, _, _)| *p == canonical) {
+                return Err(anyhow!("Cyclic base file `{}`!", path.display()));
+            }
+
+            let mut value: sj::Value = sj::from_str(&std::fs::read_to_string(&path)?)?;
+            let report =
+                migration::migrate(&mut value).map_err(|e| anyhow!("{}: {e}", path.display()))?;
+            let base = value
+                .get("base")
+                .and_then(|b| b.as_str())
+                .map(|b| canonical.parent().unwrap_or(Path::new("")).join(b));
+            layers.push((canonical, value, report));
+
+            match base {
+                Some(base) => path = base,
+                None => break,
+            }
+        }
+        layers.reverse();
+
+        Ok(Self { layers })
+    }
+
+    fn merged(&self, n: usize) -> Option<sj::Value> {
+        let mut iter = self.layers.iter().take(n).map(|(_, val, _)| val.clone());
+        iter.next().map(|mut value| {
+            iter.for_each(|layer| merge_value(&mut value, layer));
+            value
+        })
+    }
+
+    pub fn base_value(&self) -> Option<sj::Value> {
+        self.merged(self.layers.len() - 1)
+    }
+
+    pub fn resolve(&self) -> Result<FasFile> {
+        let mut value = self.merged(self.layers.len()).unwrap_or_default();
+        if let Some(top) = self.layers.last() {
+            match top.1.get("base") {
+                Some(base) => value["base"] = base.clone(),
+                None => {
+                    value.as_object_mut().map(|obj| obj.remove("base"));
+                }
+            }
+        }
+        Ok(sj::from_value(value)?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,14 +241,66 @@ mod tests {
         };
         test_file.strucs.insert("一".to_string(), struc);
 
-        let tmp_dir = std::path::Path::new("tmp");
-        if !tmp_dir.exists() {
-            std::fs::create_dir(tmp_dir).unwrap();
-        }
+        let tmp_dir = tempfile::tempdir().unwrap();
         std::fs::write(
-            tmp_dir.join("fas_file.fas.json"),
+            tmp_dir.path().join("fas_file.fas.json"),
             serde_json::to_string_pretty(&test_file).unwrap(),
         )
         .unwrap();
     }
+
+    #[test]
+    fn test_layers() {
+        use crate::base::*;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tmp_dir = dir.path();
+        let path = |name: &str| tmp_dir.join(name).to_str().unwrap().to_string();
+
+        let mut regular = FasFile {
+            name: "regular".to_string(),
+            ..Default::default()
+        };
+        for (name, x) in [("一", 4), ("二", 2)] {
+            regular.strucs.insert(
+                name.to_string(),
+                StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(x, 0)])]),
+            );
+        }
+        regular.save_pretty(&path("regular.fas.json")).unwrap();
+        std::fs::write(
+            tmp_dir.join("bold.fas.json"),
+            sj::json!({
+                "name": "bold",
+                "base": "regular.fas.json",
+                "strucs": { "二": { "paths": [] } },
+                "config": { "size": 0.9 }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let chain = FasChain::load(&path("bold.fas.json")).unwrap();
+        assert_eq!(chain.layers.len(), 2);
+        let mut bold = chain.resolve().unwrap();
+        assert_eq!(bold.name, "bold");
+        assert_eq!(bold.base.as_deref(), Some("regular.fas.json"));
+        assert_eq!(bold.strucs["一"].paths[0].kpoints[1].pos.x, 4);
+        assert!(bold.strucs["二"].paths.is_empty());
+        assert_eq!(bold.config.size.h, 0.9);
+
+        bold.strucs.remove("一");
+        let layer = bold.to_layer(&chain.base_value().unwrap()).unwrap();
+        assert_eq!(layer["strucs"]["一"], sj::Value::Null);
+        assert_eq!(layer["strucs"]["二"], sj::json!({ "paths": [] }));
+        assert_eq!(layer["config"], sj::json!({ "size": 0.9 }));
+        assert_eq!(layer["base"], "regular.fas.json");
+
+        std::fs::write(
+            tmp_dir.join("cycle.fas.json"),
+            sj::json!({ "base": "cycle.fas.json" }).to_string(),
+        )
+        .unwrap();
+        assert!(FasChain::load(&path("cycle.fas.json")).is_err());
+    }
 }
@@ -0,0 +1,423 @@
+use super::Service;
+use crate::{
+    base::*,
+    combination::{CompData, CompTree, StrucComb},
+    config::Config,
+};
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const EPSILON: f32 = 1e-4;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub enum LintKind {
+    // Parallel strokes of two components closer than the minimum unit in `axis`.
+    TooClose {
+        other: String,
+        axis: Axis,
+        distance: f32,
+        min: f32,
+    },
+    // Strokes of two components running over or across each other.
+    Overlap {
+        other: String,
+    },
+    // An assigned value less than its base. `slot` is like `assign[2]`, `blank[0]` or `interval[1]`.
+    UnderBase {
+        axis: Axis,
+        slot: String,
+        base: f32,
+        value: f32,
+    },
+    // A point outside the em box less the white area.
+    OutOfBounds {
+        bounds: WorkBox,
+    },
+    // Symmetric values that differ, see `combination::Asymmetry`.
+    Asymmetric {
+        axis: Axis,
+        other: Option<String>,
+    },
+}
+
+// `path` is the path of the component as in `StrucComb::node`, `pos` is where the problem is.
+#[derive(Serialize, Clone, Debug)]
+pub struct Finding {
+    pub kind: LintKind,
+    pub path: String,
+    pub name: String,
+    pub pos: Option<WorkPoint>,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct LintReport {
+    pub chars: usize,
+    pub failed: BTreeMap<char, String>,
+    pub findings: BTreeMap<char, Vec<Finding>>,
+}
+
+impl LintReport {
+    pub fn is_clean(&self) -> bool {
+        self.failed.is_empty() && self.findings.is_empty()
+    }
+
+    pub fn count(&self) -> usize {
+        self.findings.values().map(|list| list.len()).sum()
+    }
+}
+
+struct Leaf<'a> {
+    path: String,
+    name: &'a str,
+    points: Vec<WorkPoint>,
+    segments: Vec<[WorkPoint; 2]>,
+}
+
+fn leaves<'a>(tree: &'a CompTree, path: String, list: &mut Vec<Leaf<'a>>) {
+    if tree.children.is_empty() {
+        let mut leaf = Leaf {
+            path,
+            name: &tree.name,
+            points: vec![],
+            segments: vec![],
+        };
+        for kpoints in &tree.paths {
+            let points = flatten_curves(kpoints, CURVE_STEPS);
+            leaf.segments
+                .extend(points.windows(2).map(|pair| [pair[0], pair[1]]));
+            leaf.points.extend(points);
+        }
+        list.push(leaf);
+    } else {
+        for (i, c) in tree.children.iter().enumerate() {
+            leaves(c, StrucComb::child_path(&path, i), list);
+        }
+    }
+}
+
+// The axis a segment runs along, if it is horizontal or vertical.
+fn segment_axis(seg: &[WorkPoint; 2]) -> Option<Axis> {
+    let (dx, dy) = ((seg[1].x - seg[0].x).abs(), (seg[1].y - seg[0].y).abs());
+    if dy < EPSILON && dx > EPSILON {
+        Some(Axis::Horizontal)
+    } else if dx < EPSILON && dy > EPSILON {
+        Some(Axis::Vertical)
+    } else {
+        None
+    }
+}
+
+fn range(seg: &[WorkPoint; 2], axis: Axis) -> [f32; 2] {
+    let (a, b) = (*seg[0].hv_get(axis), *seg[1].hv_get(axis));
+    [a.min(b), a.max(b)]
+}
+
+fn compare_segments(
+    s1: &[WorkPoint; 2],
+    s2: &[WorkPoint; 2],
+    min_unit: DataHV<f32>,
+) -> Option<(LintKind, WorkPoint)> {
+    match (segment_axis(s1), segment_axis(s2)) {
+        (Some(a1), Some(a2)) if a1 == a2 => {
+            let (r1, r2) = (range(s1, a1), range(s2, a1));
+            let common = [r1[0].max(r2[0]), r1[1].min(r2[1])];
+            if common[1] - common[0] < EPSILON {
+                return None;
+            }
+            let cross = a1.inverse();
+            let (c1, c2) = (*s1[0].hv_get(cross), *s2[0].hv_get(cross));
+            let distance = (c1 - c2).abs();
+            let mut pos = WorkPoint::zero();
+            *pos.hv_get_mut(a1) = (common[0] + common[1]) * 0.5;
+            *pos.hv_get_mut(cross) = (c1 + c2) * 0.5;
+
+            let min = *min_unit.hv_get(cross);
+            if distance < EPSILON {
+                Some((
+                    LintKind::Overlap {
+                        other: String::new(),
+                    },
+                    pos,
+                ))
+            } else if distance < min {
+                Some((
+                    LintKind::TooClose {
+                        other: String::new(),
+                        axis: cross,
+                        distance,
+                        min,
+                    },
+                    pos,
+                ))
+            } else {
+                None
+            }
+        }
+        (Some(a1), Some(_)) => {
+            let (h, v) = match a1 {
+                Axis::Horizontal => (s1, s2),
+                Axis::Vertical => (s2, s1),
+            };
+            let (x, y) = (v[0].x, h[0].y);
+            let (hx, vy) = (range(h, Axis::Horizontal), range(v, Axis::Vertical));
+            match hx[0] + EPSILON < x
+                && x < hx[1] - EPSILON
+                && vy[0] + EPSILON < y
+                && y < vy[1] - EPSILON
+            {
+                true => Some((
+                    LintKind::Overlap {
+                        other: String::new(),
+                    },
+                    WorkPoint::new(x, y),
+                )),
+                false => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Checks the strokes of a finished tree. Only horizontal and vertical segments are compared.
+pub fn lint_tree(tree: &CompTree, config: &Config, bounds: WorkBox) -> Vec<Finding> {
+    // Without units nothing is too close, only overlaps are reported.
+    let min_unit = config
+        .units
+        .map(|units| units.iter().copied().reduce(f32::min).unwrap_or(0.0));
+    let mut list = vec![];
+    leaves(tree, String::new(), &mut list);
+
+    let mut findings = vec![];
+    for (i, l1) in list.iter().enumerate() {
+        for l2 in list.iter().skip(i + 1) {
+            for s1 in &l1.segments {
+                for s2 in &l2.segments {
+                    if let Some((mut kind, pos)) = compare_segments(s1, s2, min_unit) {
+                        match &mut kind {
+                            LintKind::TooClose { other, .. } | LintKind::Overlap { other } => {
+                                *other = l2.path.clone()
+                            }
+                            _ => {}
+                        }
+                        findings.push(Finding {
+                            kind,
+                            path: l1.path.clone(),
+                            name: l1.name.to_string(),
+                            pos: Some(pos),
+                        });
+                    }
+                }
+            }
+        }
+
+        for p in &l1.points {
+            if p.x < bounds.min.x - EPSILON
+                || p.x > bounds.max.x + EPSILON
+                || p.y < bounds.min.y - EPSILON
+                || p.y > bounds.max.y + EPSILON
+            {
+                findings.push(Finding {
+                    kind: LintKind::OutOfBounds { bounds },
+                    path: l1.path.clone(),
+                    name: l1.name.to_string(),
+                    pos: Some(*p),
+                });
+            }
+        }
+    }
+
+    findings
+}
+
+fn lint_assigns(comb: &StrucComb, path: String, findings: &mut Vec<Finding>) {
+    for axis in Axis::list() {
+        let mut check = |slot: String, val: &AssignVal| {
+            if val.total() + EPSILON < val.base {
+                findings.push(Finding {
+                    kind: LintKind::UnderBase {
+                        axis,
+                        slot,
+                        base: val.base,
+                        value: val.total(),
+                    },
+                    path: path.clone(),
+                    name: comb.name.clone(),
+                    pos: None,
+                });
+            }
+        };
+
+        for (i, val) in comb.blanks.hv_get(axis).iter().enumerate() {
+            check(format!("blank[{i}]"), val);
+        }
+        match &comb.cdata {
+            CompData::Single { assigns, .. } => {
+                for (i, val) in assigns.hv_get(axis).iter().enumerate() {
+                    check(format!("assign[{i}]"), val);
+                }
+            }
+            CompData::Scale { intervals_val, .. } => {
+                for (i, val) in intervals_val.iter().enumerate() {
+                    check(format!("interval[{i}]"), val);
+                }
+            }
+            CompData::Surround { .. } => todo!(), // surround
+        }
+    }
+
+    if let CompData::Scale { comps, .. } = &comb.cdata {
+        for (i, c) in comps.iter().enumerate() {
+            lint_assigns(c, StrucComb::child_path(&path, i), findings);
+        }
+    }
+}
+
+pub fn lint_comb(comb: &StrucComb, config: &Config) -> Vec<Finding> {
+    let mut bounds = comb.get_char_box();
+    if let Some(white) = comb.get_white_area() {
+        bounds.min.x += white.h[0];
+        bounds.max.x -= white.h[1];
+        bounds.min.y += white.v[0];
+        bounds.max.y -= white.v[1];
+    }
+
+    let mut findings = lint_tree(&comb.get_comp_tree(config.get_smooth()), config, bounds);
+    lint_assigns(comb, String::new(), &mut findings);
+    findings.extend(comb.asymmetries().into_iter().map(|asym| {
+        Finding {
+            name: comb
+                .node(&asym.path)
+                .map(|c| c.name.clone())
+                .unwrap_or_default(),
+            kind: LintKind::Asymmetric {
+                axis: asym.axis,
+                other: asym.other,
+            },
+            path: asym.path,
+            pos: None,
+        }
+    }));
+    findings
+}
+
+pub fn lint_chars(service: &impl Service, chars: &[char]) -> LintReport {
+    let mut report = LintReport {
+        chars: chars.len(),
+        ..Default::default()
+    };
+    for (chr, r) in service.gen_chars(chars) {
+        match r {
+            Ok(comb) => {
+                let findings = lint_comb(&comb, service.get_config());
+                if !findings.is_empty() {
+                    report.findings.insert(chr, findings);
+                }
+            }
+            Err(e) => {
+                report.failed.insert(chr, e.to_string());
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        construct::CstType,
+        service::test_util::{leaf, stacked_service},
+    };
+
+    #[test]
+    fn test_lint_tree() {
+        let tree = CompTree {
+            name: "x".to_string(),
+            tp: CstType::Scale(Axis::Vertical),
+            paths: vec![],
+            children: vec![
+                leaf("a", vec![vec![(0.1, 0.4), (0.9, 0.4)]]),
+                leaf(
+                    "b",
+                    vec![vec![(0.2, 0.42), (0.8, 0.42)], vec![(0.5, 0.3), (0.5, 1.1)]],
+                ),
+            ],
+            alloc_box: WorkBox::zero(),
+            ink_box: None,
+            blanks: Default::default(),
+            metrics: None,
+        };
+        let config = Config::default();
+        let bounds = WorkBox::new(WorkPoint::zero(), WorkPoint::splat(1.0));
+        let findings = lint_tree(&tree, &config, bounds);
+
+        let kinds: Vec<_> = findings.iter().map(|f| &f.kind).collect();
+        assert_eq!(findings.len(), 3, "{kinds:?}");
+        assert!(matches!(
+            &findings[0].kind,
+            LintKind::TooClose { other, axis: Axis::Vertical, .. } if other == "1"
+        ));
+        assert_eq!(findings[0].path, "0");
+        assert!(matches!(&findings[1].kind, LintKind::Overlap { other } if other == "1"));
+        assert_eq!(findings[1].pos, Some(WorkPoint::new(0.5, 0.4)));
+        assert_eq!(findings[2].kind, LintKind::OutOfBounds { bounds });
+        assert_eq!(findings[2].path, "1");
+        assert_eq!(findings[2].pos, Some(WorkPoint::new(0.5, 1.1)));
+
+        let mut config = Config::default();
+        config.units = Default::default();
+        let findings = lint_tree(&tree, &config, bounds);
+        assert_eq!(findings.len(), 2);
+        assert!(
+            findings
+                .iter()
+                .all(|f| !matches!(f.kind, LintKind::TooClose { .. }))
+        );
+    }
+
+    #[test]
+    fn test_lint_chars() {
+        let service = stacked_service();
+
+        let report = lint_chars(&service, &['一', '二', '三']);
+        assert_eq!(report.chars, 3);
+        assert!(report.failed.contains_key(&'三'));
+        assert!(!report.findings.contains_key(&'一'));
+
+        let mut comb = service
+            .get_struc_comb(service.get_char_tree("一".to_string()))
+            .unwrap();
+        comb.blanks.h[0] = AssignVal {
+            base: 0.1,
+            excess: -0.1,
+        };
+        let findings = lint_comb(&comb, service.get_config());
+        assert!(
+            matches!(
+                &findings[..],
+                [Finding { kind: LintKind::UnderBase { axis: Axis::Horizontal, slot, .. }, .. }]
+                    if slot == "blank[0]"
+            ),
+            "{findings:?}"
+        );
+
+        let mut comb = service
+            .get_struc_comb(service.get_char_tree("二".to_string()))
+            .unwrap();
+        if let Some(CompData::Single { assigns, .. }) = comb.node_mut("1").map(|c| &mut c.cdata) {
+            let total = assigns.h[0].total();
+            assigns.h[0].uncheck_set(total + 0.01);
+        }
+        let findings = lint_comb(&comb, service.get_config());
+        assert!(
+            findings.iter().any(|f| f.path == "1"
+                && f.kind
+                    == LintKind::Asymmetric {
+                        axis: Axis::Horizontal,
+                        other: Some("0".to_string())
+                    }),
+            "{findings:?}"
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
     combination::StrucProto,
+    config::Strictness,
     construct::CstTable,
     service::{
-        Service,
-        fas::{FasFile, Strucs},
+        CharOverride, Service,
+        ctrls::{CtrlRegistry, SpaceCtrl},
+        fas::{FasChain, FasFile, Strucs},
     },
 };
 use anyhow::Result;
@@ -11,7 +13,10 @@ use anyhow::Result;
 pub struct LocalService {
     changed: bool,
     source: Option<FasFile>,
+    base: Option<serde_json::Value>,
+    ctrls: CtrlRegistry,
     table: CstTable,
+    dir: Option<std::path::PathBuf>,
 }
 
 impl LocalService {
@@ -20,15 +25,18 @@ impl LocalService {
             changed: false,
             table: table,
             source: None,
+            base: None,
+            ctrls: Default::default(),
+            dir: None,
         }
     }
 
     pub fn standard() -> Self {
-        Self {
-            changed: false,
-            table: CstTable::standard(),
-            source: None,
-        }
+        Self::new(CstTable::standard())
+    }
+
+    pub fn register_space_ctrl(&mut self, name: impl Into<String>, ctrl: impl SpaceCtrl + 'static) {
+        self.ctrls.register(name, ctrl);
     }
 
     pub fn source(&self) -> Option<&FasFile> {
@@ -49,6 +57,16 @@ impl LocalService {
         }
     }
 
+    // Saves only the overriding layer if the source has a base file.
+    pub fn save_layer(&mut self, path: &str) -> Result<()> {
+        match (&self.source, &self.base) {
+            (Some(source), Some(base)) => source.save_layer(path, base).map(|_| {
+                self.changed = false;
+            }),
+            _ => self.save(path),
+        }
+    }
+
     pub fn save_struc(&mut self, name: String, struc: StrucProto) {
         if let Some(source) = &mut self.source {
             source.strucs.insert(name, struc);
@@ -57,18 +75,30 @@ impl LocalService {
     }
 
     pub fn load_fas(&mut self, data: FasFile) {
+        if data.config.strictness() != Strictness::Ignore {
+            data.config
+                .issues()
+                .iter()
+                .for_each(|issue| log::warn!("Config {issue}"));
+        }
         self.source = Some(data);
+        self.base = None;
+        self.dir = None;
         self.changed = false;
     }
 
     pub fn load_file(&mut self, path: &str) -> Result<()> {
-        match FasFile::from_file(path) {
-            Ok(data) => {
-                self.load_fas(data);
-                Ok(())
-            }
-            Err(e) => Err(e),
-        }
+        let chain = FasChain::load(path)?;
+        chain
+            .layers
+            .iter()
+            .filter(|(_, _, report)| report.is_changed())
+            .for_each(|(path, _, report)| log::info!("{}: {report}", path.display()));
+
+        self.load_fas(chain.resolve()?);
+        self.base = chain.base_value();
+        self.dir = std::path::Path::new(path).parent().map(|p| p.to_path_buf());
+        Ok(())
     }
 }
 
@@ -81,7 +111,19 @@ impl Service for LocalService {
         &self.source.as_ref().unwrap().config
     }
 
+    fn get_override(&self, name: &str) -> Option<&CharOverride> {
+        self.source.as_ref().and_then(|s| s.overrides.get(name))
+    }
+
+    fn get_space_ctrl(&self, name: &str) -> Option<&dyn SpaceCtrl> {
+        self.ctrls.get(name)
+    }
+
     fn get_table(&self) -> &CstTable {
         &self.table
     }
+
+    fn source_dir(&self) -> Option<&std::path::Path> {
+        self.dir.as_deref()
+    }
 }
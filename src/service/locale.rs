@@ -0,0 +1,192 @@
+use crate::{
+    combination::StrucProto,
+    config::Config,
+    construct::{CstTable, Locale},
+    service::{CharOverride, Service, ctrls::SpaceCtrl, fas::Strucs},
+};
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+pub struct LocaleService<'a, S: Service> {
+    inner: &'a S,
+    locale: Locale,
+}
+
+impl<'a, S: Service> LocaleService<'a, S> {
+    pub fn new(inner: &'a S, locale: Locale) -> Self {
+        Self { inner, locale }
+    }
+}
+
+impl<'a, S: Service> Service for LocaleService<'a, S> {
+    fn get_table(&self) -> &CstTable {
+        self.inner.get_table()
+    }
+
+    fn get_config(&self) -> &Config {
+        self.inner.get_config()
+    }
+
+    fn get_strucs(&self) -> &Strucs {
+        self.inner.get_strucs()
+    }
+
+    fn get_locale(&self) -> Option<Locale> {
+        Some(self.locale)
+    }
+
+    fn get_struc_proto(&self, name: &str) -> Option<&StrucProto> {
+        self.inner
+            .get_strucs()
+            .get(&self.locale.tagged_name(name))
+            .or_else(|| self.inner.get_struc_proto(name))
+    }
+
+    fn get_override(&self, name: &str) -> Option<&CharOverride> {
+        self.inner.get_override(name)
+    }
+
+    fn get_space_ctrl(&self, name: &str) -> Option<&dyn SpaceCtrl> {
+        self.inner.get_space_ctrl(name)
+    }
+
+    fn source_dir(&self) -> Option<&Path> {
+        self.inner.source_dir()
+    }
+}
+
+pub fn locale_variants(service: &impl Service) -> BTreeMap<Locale, Vec<char>> {
+    let cfg = service.get_config();
+    let tagged: BTreeSet<(Locale, &str)> = service
+        .get_table()
+        .keys()
+        .chain(cfg.supplement.keys())
+        .chain(service.get_strucs().keys())
+        .filter_map(|name| Locale::split_tagged(name).map(|(base, locale)| (locale, base)))
+        .collect();
+
+    tagged
+        .into_iter()
+        .fold(BTreeMap::new(), |mut map, (locale, base)| {
+            let chars: &mut BTreeSet<char> = map.entry(locale).or_default();
+            chars.extend(service.filter_comps_relate(base));
+            map
+        })
+        .into_iter()
+        .map(|(locale, chars)| (locale, chars.into_iter().collect()))
+        .collect()
+}
+
+// Glyphs of variants are named `uniXXXX.loclJ`
+pub fn glyph_name(chr: char, locale: Option<Locale>) -> String {
+    let code = chr as u32;
+    let name = if code > 0xFFFF {
+        format!("u{code:05X}")
+    } else {
+        format!("uni{code:04X}")
+    };
+    match locale {
+        Some(locale) => format!("{name}.locl{}", locale.symbol()),
+        None => name,
+    }
+}
+
+// OpenType feature file for `locl` substitutions
+pub fn locl_feature(variants: &BTreeMap<Locale, Vec<char>>) -> String {
+    let mut fea = String::from("languagesystem DFLT dflt;\nlanguagesystem hani dflt;\n");
+    for locale in variants.keys() {
+        fea.push_str(&format!("languagesystem hani {};\n", locale.language_tag()));
+    }
+
+    for (locale, chars) in variants.iter().filter(|(_, chars)| !chars.is_empty()) {
+        fea.push_str(&format!("\nlookup locl_{} {{\n", locale.symbol()));
+        for &chr in chars {
+            fea.push_str(&format!(
+                "    sub {} by {};\n",
+                glyph_name(chr, None),
+                glyph_name(chr, Some(*locale))
+            ));
+        }
+        fea.push_str(&format!("}} locl_{};\n", locale.symbol()));
+    }
+
+    fea.push_str("\nfeature locl {\n    script hani;\n");
+    for (locale, _) in variants.iter().filter(|(_, chars)| !chars.is_empty()) {
+        fea.push_str(&format!(
+            "    language {};\n    lookup locl_{};\n",
+            locale.language_tag(),
+            locale.symbol()
+        ));
+    }
+    fea.push_str("} locl;\n");
+
+    fea
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::*,
+        construct::{Component, CpAttrs, CstType},
+        service::{SimpleService, test_util::local_service},
+    };
+
+    #[test]
+    fn test_locale_service() {
+        let mut table = CstTable::empty();
+        table.insert(
+            "滑".to_string(),
+            CpAttrs {
+                tp: CstType::Scale(Axis::Horizontal),
+                components: vec![Component::from_name("氵"), Component::from_name("骨")],
+            },
+        );
+        table.insert("骨".to_string(), CpAttrs::single());
+
+        let mut service = SimpleService::new(table);
+        service.config.supplement.insert(
+            Locale::J.tagged_name("骨"),
+            CpAttrs {
+                tp: CstType::Scale(Axis::Vertical),
+                components: vec![Component::from_name("冎"), Component::from_name("月")],
+            },
+        );
+        service.strucs.insert(
+            Locale::T.tagged_name("氵"),
+            StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(0, 2)])]),
+        );
+
+        let tree = service.get_char_tree("骨".to_string());
+        assert_eq!(tree.tp, CstType::Single);
+        let tree = LocaleService::new(&service, Locale::J).get_char_tree("骨".to_string());
+        assert_eq!(tree.tp, CstType::Scale(Axis::Vertical));
+        assert_eq!(tree.children[1].name, "月");
+
+        assert!(service.get_struc_proto("氵").is_none());
+        assert!(
+            LocaleService::new(&service, Locale::T)
+                .get_struc_proto("氵")
+                .is_some()
+        );
+
+        let variants = locale_variants(&service);
+        assert_eq!(variants[&Locale::J], vec!['滑', '骨']);
+        assert_eq!(variants[&Locale::T], vec!['滑']);
+
+        let fea = locl_feature(&variants);
+        assert!(fea.contains("sub uni9AA8 by uni9AA8.loclJ;"));
+        assert!(fea.contains("language ZHT;\n    lookup locl_T;"));
+    }
+
+    #[test]
+    fn test_source_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = local_service(dir.path());
+        let service = LocaleService::new(&local, Locale::J);
+        assert_eq!(service.source_dir(), Some(dir.path()));
+    }
+}
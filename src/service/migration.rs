@@ -0,0 +1,170 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use serde_json as sj;
+
+pub const CURRENT_VERSION: [u32; 2] = [0, 2];
+
+pub fn version_str(version: [u32; 2]) -> String {
+    format!("{}.{}", version[0], version[1])
+}
+
+// `major.minor` or `major`.
+pub fn parse_version(version: &str) -> Result<[u32; 2]> {
+    let numbers = version
+        .split('.')
+        .map(|n| n.trim().parse::<u32>())
+        .collect::<Result<Vec<u32>, _>>()
+        .map_err(|e| anyhow!("Invalid version `{version}`: {e}"))?;
+    match numbers[..] {
+        [major] => Ok([major, 0]),
+        [major, minor] => Ok([major, minor]),
+        _ => Err(anyhow!("Invalid version `{version}`!")),
+    }
+}
+
+pub struct Migration {
+    pub from: [u32; 2],
+    pub to: [u32; 2],
+    pub note: &'static str,
+    pub apply: fn(&mut sj::Value, &mut Vec<String>),
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MigrationReport {
+    pub from: [u32; 2],
+    pub to: [u32; 2],
+    pub changes: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn is_changed(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+impl std::fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Migrated from {} to {}",
+            version_str(self.from),
+            version_str(self.to)
+        )?;
+        for change in &self.changes {
+            write!(f, "\n  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+fn strucs_attrs<F>(data: &mut sj::Value, mut f: F)
+where
+    F: FnMut(&str, &mut sj::value::Map<String, sj::Value>),
+{
+    if let Some(strucs) = data.get_mut("strucs").and_then(|s| s.as_object_mut()) {
+        for (name, proto) in strucs.iter_mut() {
+            if let Some(attrs) = proto.get_mut("attrs").and_then(|a| a.as_object_mut()) {
+                f(name, attrs);
+            }
+        }
+    }
+}
+
+fn rename_fixed_alloc(data: &mut sj::Value, changes: &mut Vec<String>) {
+    strucs_attrs(data, |name, attrs| {
+        if let Some(val) = attrs.remove("fixed_Alloc") {
+            changes.push(format!(
+                "/strucs/{name}/attrs: renamed `fixed_Alloc` to `fixed_alloc`"
+            ));
+            attrs.insert("fixed_alloc".to_string(), val);
+        }
+    });
+}
+
+pub fn migrations() -> Vec<Migration> {
+    vec![Migration {
+        from: [0, 1],
+        to: [0, 2],
+        note: "Attribute key `fixed_Alloc` is renamed to `fixed_alloc`.",
+        apply: rename_fixed_alloc,
+    }]
+}
+
+// Upgrades a `FasFile` document in place and stamps the current version.
+pub fn migrate(data: &mut sj::Value) -> Result<MigrationReport> {
+    let from = match data.get("version") {
+        Some(sj::Value::String(version)) => parse_version(version)?,
+        Some(val) => return Err(anyhow!("Invalid version `{val}`!")),
+        None => [0, 1],
+    };
+    if from > CURRENT_VERSION {
+        return Err(anyhow!(
+            "Version {} is newer than the supported {}!",
+            version_str(from),
+            version_str(CURRENT_VERSION)
+        ));
+    }
+
+    let mut report = MigrationReport {
+        from,
+        to: from,
+        changes: vec![],
+    };
+    for step in migrations() {
+        if report.to < step.to && report.to >= step.from {
+            (step.apply)(data, &mut report.changes);
+            report.to = step.to;
+        }
+    }
+    report.to = CURRENT_VERSION;
+
+    if let Some(obj) = data.as_object_mut() {
+        obj.insert("version".to_string(), version_str(CURRENT_VERSION).into());
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        let mut data = sj::json!({
+            "name": "test",
+            "version": "0.1",
+            "strucs": {
+                "一": {
+                    "paths": [],
+                    "attrs": { "fixed_Alloc": { "h": [0], "v": [] } }
+                }
+            },
+            "config": {}
+        });
+
+        let report = migrate(&mut data).unwrap();
+        assert_eq!(report.from, [0, 1]);
+        assert_eq!(report.to, CURRENT_VERSION);
+        assert_eq!(report.changes.len(), 1);
+        assert_eq!(data["version"], version_str(CURRENT_VERSION));
+        assert!(data["strucs"]["一"]["attrs"].get("fixed_Alloc").is_none());
+        assert_eq!(
+            data["strucs"]["一"]["attrs"]["fixed_alloc"],
+            sj::json!({ "h": [0], "v": [] })
+        );
+
+        let report = migrate(&mut data).unwrap();
+        assert!(!report.is_changed());
+        assert!(report.changes.is_empty());
+
+        data["version"] = "9.0".into();
+        assert!(migrate(&mut data).is_err());
+        for version in ["abc", "0.1.2", "", "0.x"] {
+            data["version"] = version.into();
+            assert!(migrate(&mut data).is_err(), "{version}");
+            assert_eq!(data["version"], version);
+        }
+        assert_eq!(parse_version("1").unwrap(), [1, 0]);
+    }
+}
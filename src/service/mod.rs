@@ -2,41 +2,94 @@ pub mod algorithm;
 mod combination;
 mod space;
 
+pub mod ctrls;
+pub mod diff;
 pub mod fas;
+pub mod lint;
 pub mod local;
+pub mod locale;
+pub mod migration;
+pub mod overrides;
+pub mod schema;
+pub mod smooth;
+pub mod snapshot;
+pub mod solver;
+pub mod stats;
+pub mod trace;
+
+#[cfg(test)]
+mod test_util;
 
 use crate::{
-    combination::{StrucComb, StrucProto},
+    combination::{CompTree, StrucComb, StrucProto},
     config::Config,
-    construct::{CharTree, Component, CpAttrs, CstError, CstTable, CstType},
+    construct::{
+        CharSet, CharSetError, CharSets, CharTree, Component, Coverage, CpAttrs, CstError,
+        CstTable, CstType, Locale,
+    },
 };
 
+use std::collections::BTreeMap;
+
 pub trait Service {
     fn get_table(&self) -> &CstTable;
     fn get_config(&self) -> &Config;
     fn get_strucs(&self) -> &fas::Strucs;
 
+    fn get_locale(&self) -> Option<Locale> {
+        None
+    }
+
     fn get_struc_proto(&self, name: &str) -> Option<&StrucProto> {
         self.get_strucs().get(name)
     }
 
+    fn get_override(&self, _name: &str) -> Option<&CharOverride> {
+        None
+    }
+
+    fn get_space_ctrl(&self, name: &str) -> Option<&dyn ctrls::SpaceCtrl> {
+        ctrls::builtin(name)
+    }
+
     fn get_char_tree(&self, name: String) -> CharTree
     where
         Self: Sized,
     {
-        combination::get_char_tree(self, name)
+        combination::get_char_tree(self, name, &mut trace::Tracer::disabled())
     }
 
     fn get_struc_comb(&self, target: CharTree) -> Result<StrucComb, CstError>
     where
         Self: Sized,
     {
-        let mut comb = combination::get_comb_proto_in(self, target, Default::default())?;
-        let (assigns, _) = combination::check_space(self, &mut comb)?;
-        combination::assign_space(self, &mut comb, assigns);
-        combination::process_space(self, &mut comb);
+        combination::gen_struc_comb(self, target, &mut trace::Tracer::disabled())
+    }
+
+    // Runs the space controls on a comb, e.g. one edited after loading it with
+    // `StrucComb::from_saved`.
+    fn process_comb(&self, comb: &mut StrucComb)
+    where
+        Self: Sized,
+    {
+        combination::process_space(self, comb, &mut trace::Tracer::disabled())
+    }
 
-        Ok(comb)
+    // The output paths of a comb, smoothed into curves if the config has a `smooth` setting.
+    fn get_comp_tree(&self, comb: &StrucComb) -> CompTree {
+        comb.get_comp_tree(self.get_config().get_smooth())
+    }
+
+    fn get_struc_comb_traced(
+        &self,
+        target: CharTree,
+    ) -> (Result<StrucComb, CstError>, Vec<trace::TraceRecord>)
+    where
+        Self: Sized,
+    {
+        let mut tracer = trace::Tracer::new();
+        let r = combination::gen_struc_comb(self, target, &mut tracer);
+        (r, tracer.finish())
     }
 
     fn target_chars(&self) -> Vec<char> {
@@ -55,6 +108,61 @@ pub trait Service {
             .collect()
     }
 
+    // The directory of the fas file, which relative paths in it are resolved against.
+    fn source_dir(&self) -> Option<&std::path::Path> {
+        None
+    }
+
+    fn get_charsets(&self) -> Result<CharSets, CharSetError> {
+        self.get_config().get_charsets(self.source_dir())
+    }
+
+    fn target_chars_in(&self, set: &CharSet) -> Vec<char> {
+        self.target_chars()
+            .into_iter()
+            .filter(|chr| set.contains(chr))
+            .collect()
+    }
+
+    fn gen_chars(&self, chars: &[char]) -> Vec<(char, Result<StrucComb, CstError>)>
+    where
+        Self: Sized,
+    {
+        chars
+            .iter()
+            .map(|&chr| {
+                let tree = self.get_char_tree(chr.to_string());
+                (chr, self.get_struc_comb(tree))
+            })
+            .collect()
+    }
+
+    fn coverage(&self, set: &CharSet) -> Coverage
+    where
+        Self: Sized,
+    {
+        let targets = self.target_chars_in(set);
+        let mut coverage = Coverage {
+            total: set.len(),
+            missing: set
+                .difference(&targets.iter().copied().collect())
+                .iter()
+                .copied()
+                .collect(),
+            ..Default::default()
+        };
+
+        for (chr, r) in self.gen_chars(&targets) {
+            match r {
+                Ok(_) => coverage.generated.push(chr),
+                Err(e) => {
+                    coverage.failed.insert(chr, e.to_string());
+                }
+            }
+        }
+        coverage
+    }
+
     fn taget_char_trees(&self) -> Vec<CharTree>
     where
         Self: Sized,
@@ -109,10 +217,14 @@ pub trait Service {
 }
 
 pub use local::LocalService;
+pub use locale::LocaleService;
+pub use overrides::{CharOverride, PatchedService};
 
 pub struct SimpleService {
     pub config: Config,
     pub strucs: fas::Strucs,
+    pub overrides: BTreeMap<String, CharOverride>,
+    pub ctrls: ctrls::CtrlRegistry,
     table: CstTable,
 }
 
@@ -121,16 +233,14 @@ impl SimpleService {
         Self {
             config: Default::default(),
             strucs: Default::default(),
+            overrides: Default::default(),
+            ctrls: Default::default(),
             table,
         }
     }
 
     pub fn standard() -> Self {
-        Self {
-            config: Default::default(),
-            strucs: Default::default(),
-            table: CstTable::standard(),
-        }
+        Self::new(CstTable::standard())
     }
 }
 
@@ -146,6 +256,14 @@ impl Service for SimpleService {
     fn get_table(&self) -> &CstTable {
         &self.table
     }
+
+    fn get_override(&self, name: &str) -> Option<&CharOverride> {
+        self.overrides.get(name)
+    }
+
+    fn get_space_ctrl(&self, name: &str) -> Option<&dyn ctrls::SpaceCtrl> {
+        self.ctrls.get(name)
+    }
 }
 
 #[cfg(test)]
@@ -188,6 +306,29 @@ mod tests {
         assert_eq!(service.target_chars(), vec!['艹']);
     }
 
+    #[test]
+    fn test_coverage() {
+        let mut table = CstTable::empty();
+        for name in ["一", "二"] {
+            table.insert(name.to_string(), CpAttrs::single());
+        }
+
+        let mut service = SimpleService::new(table);
+        service.strucs.insert(
+            "一".to_string(),
+            StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]),
+        );
+
+        let set = CharSet::from_text("一二三");
+        assert_eq!(service.target_chars_in(&set), vec!['一', '二']);
+
+        let coverage = service.coverage(&set);
+        assert_eq!(coverage.total, 3);
+        assert_eq!(coverage.generated, vec!['一']);
+        assert_eq!(coverage.missing, vec!['三']);
+        assert!(coverage.failed.contains_key(&'二'));
+    }
+
     #[test]
     fn test_type_remap() {
         use serde_json::json;
@@ -295,9 +436,12 @@ mod tests {
             &service,
             CharTree::new_single("t1".to_string()),
             Default::default(),
+            &mut trace::Tracer::disabled(),
         )
         .unwrap();
-        let (assigns, levels) = combination::check_space(&service, &mut comb_t1).unwrap();
+        let (assigns, levels) =
+            combination::check_space(&service, &mut comb_t1, &mut trace::Tracer::disabled())
+                .unwrap();
         let offsets = comb_t1.get_white_area().unwrap();
 
         assert!((assigns.h - 0.4).abs() < OFFSET, "{}", assigns.h);
@@ -314,7 +458,12 @@ mod tests {
         assert_eq!(levels.h, levels.v);
         assert_eq!(levels.h, 0);
 
-        combination::assign_space(&service, &mut comb_t1, assigns);
+        combination::assign_space(
+            &service,
+            &mut comb_t1,
+            assigns,
+            &mut trace::Tracer::disabled(),
+        );
         match &comb_t1.cdata {
             CompData::Single { assigns, .. } => {
                 let mut assigns: Vec<f32> = assigns
@@ -349,9 +498,12 @@ mod tests {
             &service,
             CharTree::new_single("level2".to_string()),
             Default::default(),
+            &mut trace::Tracer::disabled(),
         )
         .unwrap();
-        let (assigns, levels) = combination::check_space(&service, &mut comb_level2).unwrap();
+        let (assigns, levels) =
+            combination::check_space(&service, &mut comb_level2, &mut trace::Tracer::disabled())
+                .unwrap();
         let offsets = comb_level2.get_white_area().unwrap();
 
         assert!((assigns.h - 0.8).abs() < OFFSET, "{}", assigns.h);
@@ -372,9 +524,12 @@ mod tests {
             &service,
             CharTree::new_single("level2".to_string()),
             Default::default(),
+            &mut trace::Tracer::disabled(),
         )
         .unwrap();
-        let (assigns, levels) = combination::check_space(&service, &mut comb_level2).unwrap();
+        let (assigns, levels) =
+            combination::check_space(&service, &mut comb_level2, &mut trace::Tracer::disabled())
+                .unwrap();
         let offsets = comb_level2.get_white_area().unwrap();
         assert_eq!(comb_level2.get_bases_length(Axis::Horizontal, false), 8);
         assert_eq!(levels.h, 0);
@@ -382,5 +537,150 @@ mod tests {
         let length =
             offsets.hv_get(Axis::Horizontal).iter().sum::<f32>() + assigns.hv_get(Axis::Horizontal);
         assert!((length - 1.0).abs() < OFFSET, "{}", length);
+
+        let (r, records) =
+            service.get_struc_comb_traced(CharTree::new_single("level2".to_string()));
+        assert!(r.is_ok());
+        assert!(records.contains(&trace::TraceRecord {
+            node: String::new(),
+            comp: "level2".to_string(),
+            event: trace::TraceEvent::Reduce {
+                axis: Axis::Horizontal,
+                from: 9,
+                to: 8
+            }
+        }));
+        assert!(records.iter().any(|r| matches!(
+            r.event,
+            trace::TraceEvent::Level {
+                axis: Axis::Horizontal,
+                level: 0,
+                base_len: 8,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn test_symmetry() {
+        let mut service = SimpleService::new(CstTable::empty());
+        service.strucs.insert(
+            "一".to_string(),
+            StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]),
+        );
+        // Lengthens the first allocation of the first `一`.
+        service
+            .ctrls
+            .register("skew", |comb: &mut StrucComb, _: &serde_json::Value| {
+                if let Some(crate::combination::CompData::Single { assigns, .. }) =
+                    comb.node_mut("0").map(|c| &mut c.cdata)
+                {
+                    let total = assigns.h[0].total();
+                    assigns.h[0].uncheck_set(total + 0.01);
+                }
+            });
+        let asymmetries = |service: &SimpleService| -> Vec<trace::TraceRecord> {
+            let (r, records) = service.get_struc_comb_traced(CharTree {
+                name: "二".to_string(),
+                tp: CstType::Scale(Axis::Horizontal),
+                children: vec![
+                    CharTree::new_single("一".to_string()),
+                    CharTree::new_single("一".to_string()),
+                ],
+            });
+            assert!(r.is_ok());
+            records
+                .into_iter()
+                .filter(|r| matches!(r.event, trace::TraceEvent::Asymmetry { .. }))
+                .collect()
+        };
+
+        service.config = serde_json::from_value(serde_json::json!({
+            "space_ctrls": { "order": ["skew"], "skew": {} }
+        }))
+        .unwrap();
+        assert!(asymmetries(&service).iter().any(|r| r.node == "1"
+            && r.comp == "一"
+            && r.event
+                == trace::TraceEvent::Asymmetry {
+                    axis: Axis::Horizontal,
+                    other: Some("0".to_string())
+                }));
+
+        service.config = serde_json::from_value(serde_json::json!({
+            "space_ctrls": { "order": ["skew"], "skew": {} },
+            "space_assign": { "symmetry": true }
+        }))
+        .unwrap();
+        assert!(asymmetries(&service).is_empty());
+    }
+
+    #[test]
+    fn test_process_comb() {
+        let mut service = SimpleService::new(CstTable::empty());
+        service.strucs.insert(
+            "一".to_string(),
+            StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]),
+        );
+        service
+            .ctrls
+            .register("skew", |comb: &mut StrucComb, _: &serde_json::Value| {
+                if let crate::combination::CompData::Single { assigns, .. } = &mut comb.cdata {
+                    let total = assigns.h[0].total();
+                    assigns.h[0].uncheck_set(total + 0.01);
+                }
+            });
+        let gen_yi = |service: &SimpleService| {
+            service
+                .get_struc_comb(CharTree::new_single("一".to_string()))
+                .unwrap()
+        };
+
+        let saved = gen_yi(&service).to_saved().unwrap();
+        service.config = serde_json::from_value(serde_json::json!({
+            "space_ctrls": { "order": ["skew"], "skew": {} }
+        }))
+        .unwrap();
+        let mut loaded = StrucComb::from_saved(saved).unwrap();
+        service.process_comb(&mut loaded);
+        assert_eq!(
+            loaded.to_saved().unwrap(),
+            gen_yi(&service).to_saved().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_trace_paths() {
+        let mut service = SimpleService::new(CstTable::empty());
+        let proto = StrucProto::from(vec![
+            KeyPath::from([key_pos(0, 0), key_pos(2, 0)]),
+            KeyPath::from([key_pos(1, 0), key_pos(1, 2)]),
+        ]);
+        service.strucs.insert("丅".to_string(), proto.clone());
+        service.strucs.insert("丄".to_string(), proto);
+
+        let (r, records) = service.get_struc_comb_traced(CharTree {
+            name: "二".to_string(),
+            tp: CstType::Scale(Axis::Horizontal),
+            children: vec![
+                CharTree::new_single("丅".to_string()),
+                CharTree::new_single("丄".to_string()),
+            ],
+        });
+        let comb = r.unwrap();
+        assert!(!records.is_empty());
+        for record in records.iter() {
+            assert_eq!(comb.node(&record.node).unwrap().name, record.comp);
+        }
+        assert!(records.iter().any(|r| r.node == "0"
+            && matches!(&r.event, trace::TraceEvent::Interval { next, .. } if next == "丄")));
+        assert!(records.iter().any(|r| r.node == "1"
+            && matches!(
+                r.event,
+                trace::TraceEvent::Assign {
+                    axis: Axis::Horizontal,
+                    ..
+                }
+            )));
     }
 }
@@ -0,0 +1,265 @@
+use crate::{
+    base::*,
+    combination::{CompData, StrucComb, StrucProto, attrs::CompAttrs},
+    config::Config,
+    construct::{CstTable, Locale},
+    service::{Service, ctrls::SpaceCtrl, fas::Strucs},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json as sj;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+// One-off tuning of a character, keys of `attrs` and `assigns` are node paths like `0.1`.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct CharOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<sj::Value>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attrs: BTreeMap<String, CompAttrs>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub assigns: BTreeMap<String, DataHV<Option<Vec<AssignVal>>>>,
+    #[serde(skip)]
+    patched: PatchCache,
+}
+
+// The patched config with the base revision and patch it was built from.
+#[derive(Default)]
+struct PatchCache(Mutex<Option<(u64, sj::Value, Arc<Config>)>>);
+
+impl Clone for PatchCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl CharOverride {
+    // Parsed once and reused until the base config or the patch changes.
+    pub fn patched_config(&self, base: &Config) -> Result<Option<Arc<Config>>, String> {
+        let Some(patch) = &self.config else {
+            return Ok(None);
+        };
+
+        let mut cache = self.patched.0.lock().unwrap();
+        if let Some((revision, cached, config)) = cache.as_ref()
+            && *revision == base.revision()
+            && cached == patch
+        {
+            return Ok(Some(config.clone()));
+        }
+
+        let config = Arc::new(patch_config(base, patch)?);
+        *cache = Some((base.revision(), patch.clone(), config.clone()));
+        Ok(Some(config))
+    }
+
+    pub fn apply_attrs(&self, comb: &mut StrucComb) {
+        for (path, attrs) in self.attrs.iter() {
+            match comb.node_mut(path) {
+                Some(node) => {
+                    node.attrs.merge(attrs);
+                    if let CompData::Single { proto, .. } = &mut node.cdata {
+                        proto.attrs.merge(attrs);
+                    }
+                }
+                None => log::warn!("Override of `{}`: no node at `{path}`!", comb.name),
+            }
+        }
+    }
+
+    pub fn apply_assigns(&self, comb: &mut StrucComb) {
+        let name = comb.name.clone();
+        for (path, setting) in self.assigns.iter() {
+            match comb.node_mut(path).map(|node| &mut node.cdata) {
+                Some(CompData::Single { assigns, .. }) => {
+                    for axis in Axis::list() {
+                        if let Some(list) = setting.hv_get(axis) {
+                            let assigns = assigns.hv_get_mut(axis);
+                            if assigns.len() == list.len() {
+                                assigns.clone_from(list);
+                            } else {
+                                log::warn!(
+                                    "Override of `{name}`: expected {} assigns in {axis:?} at `{path}`, found {}!",
+                                    assigns.len(),
+                                    list.len()
+                                );
+                            }
+                        }
+                    }
+                }
+                Some(_) => log::warn!("Override of `{name}`: `{path}` is not a single node!"),
+                None => log::warn!("Override of `{name}`: no node at `{path}`!"),
+            }
+        }
+    }
+}
+
+// Fails if the patched config cannot be parsed or has issues the base config has not.
+pub fn patch_config(config: &Config, patch: &sj::Value) -> Result<Config, String> {
+    let mut data = sj::to_value(config).map_err(|e| e.to_string())?;
+    super::fas::merge_value(&mut data, patch.clone());
+    let patched: Config = sj::from_value(data).map_err(|e| e.to_string())?;
+
+    let issues: Vec<String> = patched
+        .issues()
+        .iter()
+        .filter(|issue| !config.issues().contains(issue))
+        .map(|issue| issue.to_string())
+        .collect();
+    match issues.is_empty() {
+        true => Ok(patched),
+        false => Err(issues.join("; ")),
+    }
+}
+
+pub struct PatchedService<'a, S: Service> {
+    inner: &'a S,
+    config: Arc<Config>,
+}
+
+impl<'a, S: Service> PatchedService<'a, S> {
+    pub fn new(inner: &'a S, patch: &sj::Value) -> Result<Self, String> {
+        Ok(Self::with_config(
+            inner,
+            Arc::new(patch_config(inner.get_config(), patch)?),
+        ))
+    }
+
+    pub fn with_config(inner: &'a S, config: Arc<Config>) -> Self {
+        Self { inner, config }
+    }
+}
+
+impl<'a, S: Service> Service for PatchedService<'a, S> {
+    fn get_table(&self) -> &CstTable {
+        self.inner.get_table()
+    }
+
+    fn get_config(&self) -> &Config {
+        &self.config
+    }
+
+    fn get_strucs(&self) -> &Strucs {
+        self.inner.get_strucs()
+    }
+
+    fn get_locale(&self) -> Option<Locale> {
+        self.inner.get_locale()
+    }
+
+    fn get_struc_proto(&self, name: &str) -> Option<&StrucProto> {
+        self.inner.get_struc_proto(name)
+    }
+
+    fn get_override(&self, name: &str) -> Option<&CharOverride> {
+        self.inner.get_override(name)
+    }
+
+    fn get_space_ctrl(&self, name: &str) -> Option<&dyn SpaceCtrl> {
+        self.inner.get_space_ctrl(name)
+    }
+
+    fn source_dir(&self) -> Option<&Path> {
+        self.inner.source_dir()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combination::attrs,
+        construct::CstError,
+        service::{
+            SimpleService,
+            test_util::{local_service, paired_service, paired_tree},
+        },
+    };
+
+    #[test]
+    fn test_override() {
+        let mut service = paired_service();
+        let comb = service.get_struc_comb(paired_tree()).unwrap();
+        assert!(comb.node("1").is_some());
+        assert!(comb.node("1.0").is_none());
+
+        let ovr: CharOverride = sj::from_value(sj::json!({
+            "config": { "size": 0.5 },
+            "attrs": { "1": { "main_comp": { "h": true, "v": false } } },
+            "assigns": { "0": { "h": [{ "base": 0.1, "excess": 0.0 }, { "base": 0.1, "excess": 0.1 }] } }
+        }))
+        .unwrap();
+        service.overrides.insert("二".to_string(), ovr);
+
+        let patched = PatchedService::new(&service, &sj::json!({ "size": 0.5 })).unwrap();
+        assert_eq!(patched.get_config().size.h, 0.5);
+        assert_eq!(service.get_config().size.h, 1.0);
+
+        let comb = service.get_struc_comb(paired_tree()).unwrap();
+        assert!(
+            comb.node("1")
+                .unwrap()
+                .attrs
+                .get::<attrs::MainComp>()
+                .unwrap()
+                .h
+        );
+        match &comb.node("0").unwrap().cdata {
+            CompData::Single { assigns, .. } => {
+                assert_eq!(assigns.h[1].total(), 0.2);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_override_config() {
+        let mut service = paired_service();
+        let width = |comb: &StrucComb| match &comb.node("1").unwrap().cdata {
+            CompData::Single { assigns, .. } => assigns.h.iter().map(|a| a.total()).sum::<f32>(),
+            _ => unreachable!(),
+        };
+        let set_patch = |service: &mut SimpleService, patch: sj::Value| {
+            service.overrides.insert(
+                "二".to_string(),
+                CharOverride {
+                    config: Some(patch),
+                    ..Default::default()
+                },
+            );
+        };
+
+        let base = width(&service.get_struc_comb(paired_tree()).unwrap());
+        set_patch(&mut service, sj::json!({ "zimian": [[0, 0.8]] }));
+        let patched = width(&service.get_struc_comb(paired_tree()).unwrap());
+        assert!(patched < base);
+        // Reused from the cache.
+        assert_eq!(
+            width(&service.get_struc_comb(paired_tree()).unwrap()),
+            patched
+        );
+
+        set_patch(&mut service, sj::json!({ "size": "large" }));
+        assert!(matches!(
+            service.get_struc_comb(paired_tree()),
+            Err(CstError::Override { .. })
+        ));
+        set_patch(&mut service, sj::json!({ "sise": 0.5 }));
+        assert!(matches!(
+            service.get_struc_comb(paired_tree()),
+            Err(CstError::Override { .. })
+        ));
+    }
+
+    #[test]
+    fn test_source_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = local_service(dir.path());
+        let patched = PatchedService::new(&local, &sj::json!({ "size": 0.5 })).unwrap();
+        assert_eq!(patched.source_dir(), Some(dir.path()));
+    }
+}
@@ -0,0 +1,704 @@
+use serde::Serialize;
+use serde_json::{self as sj, json};
+
+pub const SCHEMA_ID: &str = "https://github.com/chilingg/fasing/schema/fas.schema.json";
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub msg: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}`: {}", self.path, self.msg)
+    }
+}
+
+fn axis_data(item: sj::Value) -> sj::Value {
+    json!({
+        "type": "object",
+        "properties": { "h": item.clone(), "v": item },
+        "additionalProperties": false
+    })
+}
+
+fn axis_or(item: sj::Value) -> sj::Value {
+    json!({ "anyOf": [item.clone(), axis_data(item)] })
+}
+
+fn list(item: sj::Value) -> sj::Value {
+    json!({ "type": "array", "items": item })
+}
+
+fn pair(item: sj::Value) -> sj::Value {
+    json!({ "type": "array", "items": item, "minItems": 2, "maxItems": 2 })
+}
+
+fn def(name: &str) -> sj::Value {
+    json!({ "$ref": format!("#/$defs/{name}") })
+}
+
+fn hv_required(item: sj::Value) -> sj::Value {
+    let mut schema = axis_data(item);
+    schema["required"] = json!(["h", "v"]);
+    schema
+}
+
+fn comp_attrs() -> sj::Value {
+    let usize = json!({ "type": "integer", "minimum": 0 });
+    let number = json!({ "type": "number" });
+    let side_map = |item: sj::Value| {
+        json!({
+            "type": "object",
+            "properties": { "Front": item.clone(), "Back": item },
+            "additionalProperties": false
+        })
+    };
+
+    json!({
+        "type": "object",
+        "properties": {
+            "allocs": hv_required(list(usize.clone())),
+            "area_weights": {
+                "type": "object",
+                "properties": {
+                    "h": list(number.clone()),
+                    "v": list(number.clone()),
+                    "wide": list(number.clone()),
+                    "tall": list(number.clone())
+                },
+                "additionalProperties": false
+            },
+            "adjacencies": hv_required(pair(json!({ "type": "boolean" }))),
+            "in_place": list(json!({
+                "type": "array",
+                "prefixItems": [{ "type": "string" }, hv_required(list(usize.clone()))],
+                "minItems": 2,
+                "maxItems": 2
+            })),
+            "char_box": {
+                "anyOf": [
+                    { "enum": ["left", "right", "top", "bottom"] },
+                    {
+                        "type": "object",
+                        "properties": { "min": pair(number.clone()), "max": pair(number.clone()) },
+                        "required": ["min", "max"],
+                        "additionalProperties": false
+                    }
+                ]
+            },
+            "reduce_alloc": hv_required(list(list(usize.clone()))),
+            "fixed_alloc": hv_required(list(usize.clone())),
+            "interval_alloc": {
+                "type": "object",
+                "properties": {
+                    "Horizontal": side_map(list(def("intervalAlloc"))),
+                    "Vertical": side_map(list(def("intervalAlloc")))
+                },
+                "additionalProperties": false
+            },
+            "line_weight": axis_data(number.clone()),
+            "white_area": hv_required(pair(number.clone())),
+            "reduce_target": hv_required(json!({ "type": ["integer", "null"], "minimum": 0 })),
+            "main_comp": hv_required(json!({ "type": "boolean" }))
+        },
+        "additionalProperties": false
+    })
+}
+
+fn config() -> sj::Value {
+    let number = json!({ "type": "number" });
+    let string_map = json!({ "type": "object", "additionalProperties": { "type": "string" } });
+    let zimian = list(json!({
+        "type": "array",
+        "prefixItems": [{ "type": "integer", "minimum": 0 }, number.clone()],
+        "minItems": 2,
+        "maxItems": 2
+    }));
+    let side_number = json!({ "anyOf": [number.clone(), pair(number.clone())] });
+    let subarea = json!({
+        "type": "object",
+        "properties": { "factor": number.clone(), "zero": number.clone() },
+        "additionalProperties": false
+    });
+    let subcomp = json!({
+        "type": "object",
+        "properties": {
+            "factor": number.clone(),
+            "same": { "type": "boolean" },
+            "section": { "type": "array", "items": number.clone(), "minItems": 3, "maxItems": 3 }
+        },
+        "additionalProperties": false
+    });
+
+    json!({
+        "type": "object",
+        "properties": {
+            "size": axis_or(number.clone()),
+            "units": axis_or(list(number.clone())),
+            "zimian": axis_or(zimian),
+            "supplement": { "type": "object", "additionalProperties": def("cpAttrs") },
+            "type_replace": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": {
+                        "Start": string_map.clone(),
+                        "Middle": string_map.clone(),
+                        "End": string_map.clone()
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "place_replace": {
+                "type": "object",
+                "additionalProperties": list(pair(json!({ "type": "string" })))
+            },
+            "reduce_trigger": axis_or(number.clone()),
+            "replace_trigger": axis_or(number.clone()),
+            "visual_corr": axis_or(number.clone()),
+            "sharpness": axis_or(def("sharpnessModel")),
+            "reduce_replace": axis_data(string_map),
+            "space_ctrls": {
+                "type": "object",
+                "properties": {
+                    "order": list(json!({ "type": "string" })),
+                    "trend": {
+                        "type": "object",
+                        "properties": { "scale": axis_data(list(def("edgeCheck"))) },
+                        "additionalProperties": false
+                    },
+                    "subarea": axis_or(subarea),
+                    "subcomp": axis_or(subcomp),
+                    "gray": {
+                        "type": "object",
+                        "properties": {
+                            "stroke": axis_or(number.clone()),
+                            "factor": axis_or(number.clone())
+                        },
+                        "additionalProperties": false
+                    },
+                    "align": {
+                        "type": "object",
+                        "properties": { "tolerance": axis_or(number.clone()) },
+                        "additionalProperties": false
+                    }
+                }
+            },
+            "space_assign": {
+                "type": "object",
+                "properties": {
+                    "white": axis_or(side_number.clone()),
+                    "visual_corr": axis_or(side_number),
+                    "unit": axis_or(number.clone()),
+                    "mode": { "enum": ["Greedy", "Solver"] },
+                    "symmetry": { "type": "boolean" }
+                },
+                "additionalProperties": false
+            },
+            "interval": {
+                "type": "object",
+                "properties": {
+                    "limit": axis_or(number),
+                    "rules": list(def("intervalMatch"))
+                },
+                "additionalProperties": false
+            },
+            "main_edge": list(def("edgeCheck")),
+            "smooth": {
+                "type": "object",
+                "properties": {
+                    "tension": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "corner_angle": { "type": "number", "minimum": 0, "maximum": 180 },
+                    "strokes": {
+                        "type": "object",
+                        "additionalProperties": { "type": "number", "minimum": 0, "maximum": 1 }
+                    }
+                },
+                "additionalProperties": false
+            },
+            "charsets": {
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": {
+                        "chars": { "type": "string" },
+                        "file": { "type": "string" },
+                        "expr": { "type": "string" }
+                    },
+                    "additionalProperties": false
+                }
+            },
+            "strictness": { "enum": ["Ignore", "Warn", "Deny"] }
+        },
+        "additionalProperties": false
+    })
+}
+
+pub fn fas_schema() -> sj::Value {
+    let usize = json!({ "type": "integer", "minimum": 0 });
+    let weighted = json!({
+        "type": "object",
+        "properties": {
+            "min": { "type": "number" },
+            "terms": list(json!({
+                "type": "object",
+                "properties": {
+                    "weight": { "type": "number" },
+                    "when": def("edgeConditions")
+                },
+                "required": ["weight", "when"],
+                "additionalProperties": false
+            }))
+        },
+        "required": ["min", "terms"],
+        "additionalProperties": false
+    });
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": SCHEMA_ID,
+        "title": "FasFile",
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "version": { "type": "string" },
+            "base": { "type": "string" },
+            "strucs": { "type": "object", "additionalProperties": def("strucProto") },
+            "config": def("config"),
+            "overrides": { "type": "object", "additionalProperties": def("charOverride") }
+        },
+        "required": ["name", "version", "strucs", "config"],
+        "additionalProperties": false,
+        "$defs": {
+            "keyPoint": {
+                "type": "object",
+                "properties": {
+                    "pos": pair(usize),
+                    "labels": list(json!({ "type": "string" }))
+                },
+                "required": ["pos"],
+                "additionalProperties": false
+            },
+            "keyPath": {
+                "type": "object",
+                "properties": {
+                    "kpoints": list(def("keyPoint")),
+                    "hide": { "type": "boolean" }
+                },
+                "required": ["kpoints", "hide"],
+                "additionalProperties": false
+            },
+            "strucProto": {
+                "type": "object",
+                "properties": {
+                    "paths": list(def("keyPath")),
+                    "attrs": def("compAttrs")
+                },
+                "required": ["paths", "attrs"],
+                "additionalProperties": false
+            },
+            "compAttrs": comp_attrs(),
+            "edgeShape": {
+                "type": "array",
+                "items": { "type": "string" },
+                "minItems": 3,
+                "maxItems": 4
+            },
+            "edgeMatch": {
+                "anyOf": [
+                    def("edgeShape"),
+                    {
+                        "type": "object",
+                        "properties": {
+                            "shape": def("edgeShape"),
+                            "occupied": pair(json!({ "type": ["integer", "null"], "minimum": 0 })),
+                            "coverage": pair(json!({ "type": ["number", "null"] })),
+                            "depth": pair(json!({ "type": ["integer", "null"], "minimum": 0 })),
+                            "dirs": {
+                                "type": "object",
+                                "additionalProperties": pair(json!({ "type": ["integer", "null"], "minimum": 0 }))
+                            }
+                        },
+                        "additionalProperties": false
+                    }
+                ]
+            },
+            "intervalAlloc": {
+                "type": "object",
+                "properties": {
+                    "rules": list(def("edgeMatch")),
+                    "interval": { "type": "integer", "minimum": 0 },
+                    "allocs": { "type": "integer", "minimum": 0 },
+                    "requist": { "type": "boolean" },
+                    "blanks": pair(json!({ "type": "boolean" }))
+                },
+                "required": ["rules"],
+                "additionalProperties": false
+            },
+            "intervalMatch": {
+                "type": "object",
+                "properties": {
+                    "inverse": { "type": "boolean" },
+                    "axis": { "enum": ["Horizontal", "Vertical", null] },
+                    "val": { "type": "integer", "minimum": 0 },
+                    "note": { "type": "string" },
+                    "rule1": def("edgeMatch"),
+                    "rule2": def("edgeMatch")
+                },
+                "required": ["inverse", "val", "note", "rule1", "rule2"],
+                "additionalProperties": false
+            },
+            "sharpnessModel": {
+                "type": "object",
+                "properties": {
+                    "model": { "enum": ["ZeroOne", "Coverage", "Weighted", "Diagonal"] },
+                    "falloff": { "type": "number" },
+                    "weight": { "type": "number" }
+                },
+                "required": ["model"],
+                "additionalProperties": false
+            },
+            // Conditions are ANDed. Keys other than the edge conditions are switches of the
+            // setting the check belongs to, such as `state_f` of `main_edge`. `weighted` matches
+            // when the weights of the matching terms add up to `min`.
+            "edgeConditions": {
+                "type": "object",
+                "properties": {
+                    "axis": { "enum": ["Horizontal", "Vertical"] },
+                    "section": { "enum": ["Start", "Middle", "End"] },
+                    "edge1": def("edgeMatch"),
+                    "edge2": def("edgeMatch"),
+                    "edge1_cross": def("edgeMatch"),
+                    "edge2_cross": def("edgeMatch"),
+                    "front_edge": def("edgeMatch"),
+                    "front_edge_f": def("edgeMatch"),
+                    "front_edge_b": def("edgeMatch"),
+                    "back_edge": def("edgeMatch"),
+                    "back_edge_f": def("edgeMatch"),
+                    "back_edge_b": def("edgeMatch"),
+                    "any": list(def("edgeConditions")),
+                    "all": list(def("edgeConditions")),
+                    "not": def("edgeConditions"),
+                    "weighted": weighted
+                },
+                "additionalProperties": { "type": ["boolean", "null"] }
+            },
+            "edgeCheck": {
+                "type": "object",
+                "properties": {
+                    "conditions": def("edgeConditions"),
+                    "setup": pair(json!({})),
+                    "priority": { "type": "integer" },
+                    "note": {}
+                },
+                "required": ["conditions", "setup"]
+            },
+            "cpAttrs": {
+                "anyOf": [
+                    { "type": "string" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "tp": { "type": "string" },
+                            "components": list(json!({ "anyOf": [{ "type": "string" }, def("cpAttrs")] }))
+                        },
+                        "required": ["tp", "components"],
+                        "additionalProperties": false
+                    }
+                ]
+            },
+            "config": config(),
+            "assignVal": {
+                "type": "object",
+                "properties": { "base": { "type": "number" }, "excess": { "type": "number", "minimum": 0 } },
+                "required": ["base", "excess"],
+                "additionalProperties": false
+            },
+            "charOverride": {
+                "type": "object",
+                "properties": {
+                    "config": def("config"),
+                    "attrs": { "type": "object", "additionalProperties": def("compAttrs") },
+                    "assigns": {
+                        "type": "object",
+                        "additionalProperties": axis_data(json!({ "anyOf": [list(def("assignVal")), { "type": "null" }] }))
+                    }
+                },
+                "additionalProperties": false
+            }
+        }
+    })
+}
+
+pub fn validate(schema: &sj::Value, value: &sj::Value) -> Vec<SchemaViolation> {
+    let mut violations = vec![];
+    validate_in(schema, schema, value, "", &mut violations);
+    violations
+}
+
+pub fn validate_fas(value: &sj::Value) -> Vec<SchemaViolation> {
+    validate(&fas_schema(), value)
+}
+
+fn type_match(tp: &str, value: &sj::Value) -> bool {
+    match tp {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_u64() || value.is_i64(),
+        _ => false,
+    }
+}
+
+fn validate_in(
+    root: &sj::Value,
+    schema: &sj::Value,
+    value: &sj::Value,
+    path: &str,
+    violations: &mut Vec<SchemaViolation>,
+) {
+    let mut violation = |msg: String| {
+        violations.push(SchemaViolation {
+            path: path.to_string(),
+            msg,
+        })
+    };
+
+    let schema = match schema {
+        sj::Value::Bool(true) => return,
+        sj::Value::Bool(false) => return violation("Not allowed!".to_string()),
+        sj::Value::Object(obj) => obj,
+        _ => return,
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+        match reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+        {
+            Some(target) => validate_in(root, target, value, path, violations),
+            None => violation(format!("Unresolved reference `{reference}`!")),
+        }
+        return;
+    }
+
+    if let Some(tp) = schema.get("type") {
+        let ok = match tp {
+            sj::Value::String(tp) => type_match(tp, value),
+            sj::Value::Array(list) => list
+                .iter()
+                .any(|tp| tp.as_str().is_some_and(|tp| type_match(tp, value))),
+            _ => true,
+        };
+        if !ok {
+            return violation(format!("Expected type {tp}, found `{value}`!"));
+        }
+    }
+
+    if let Some(list) = schema.get("enum").and_then(|e| e.as_array())
+        && !list.contains(value)
+    {
+        violation(format!(
+            "`{value}` is not one of {}!",
+            sj::Value::Array(list.clone())
+        ));
+    }
+
+    if let Some(val) = value.as_f64() {
+        let bound = |key: &str| schema.get(key).and_then(|v| v.as_f64());
+        if let Some(min) = bound("minimum")
+            && val < min
+        {
+            violation(format!("{val} is less than {min}!"));
+        }
+        if let Some(max) = bound("maximum")
+            && val > max
+        {
+            violation(format!("{val} is greater than {max}!"));
+        }
+        if let Some(min) = bound("exclusiveMinimum")
+            && val <= min
+        {
+            violation(format!("{val} is not greater than {min}!"));
+        }
+        if let Some(max) = bound("exclusiveMaximum")
+            && val >= max
+        {
+            violation(format!("{val} is not less than {max}!"));
+        }
+    }
+
+    for key in ["anyOf", "oneOf"] {
+        if let Some(list) = schema.get(key).and_then(|l| l.as_array()) {
+            let mut results: Vec<Vec<SchemaViolation>> = list
+                .iter()
+                .map(|sub| {
+                    let mut sub_violations = vec![];
+                    validate_in(root, sub, value, path, &mut sub_violations);
+                    sub_violations
+                })
+                .collect();
+            let passed = results.iter().filter(|r| r.is_empty()).count();
+            if passed == 0 {
+                // report the closest alternative
+                results.sort_by_key(|r| r.len());
+                violations.append(&mut results.swap_remove(0));
+            } else if key == "oneOf" && passed > 1 {
+                violations.push(SchemaViolation {
+                    path: path.to_string(),
+                    msg: "Matches more than one schema of `oneOf`!".to_string(),
+                });
+            }
+        }
+    }
+
+    match value {
+        sj::Value::Object(obj) => {
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if !obj.contains_key(key) {
+                        violations.push(SchemaViolation {
+                            path: path.to_string(),
+                            msg: format!("Missing field `{key}`!"),
+                        });
+                    }
+                }
+            }
+            for (key, val) in obj {
+                let sub_path = format!("{path}/{key}");
+                match properties.and_then(|p| p.get(key)) {
+                    Some(sub) => validate_in(root, sub, val, &sub_path, violations),
+                    None => match schema.get("additionalProperties") {
+                        Some(sj::Value::Bool(false)) => violations.push(SchemaViolation {
+                            path: sub_path,
+                            msg: "Unknown key!".to_string(),
+                        }),
+                        Some(sub) => validate_in(root, sub, val, &sub_path, violations),
+                        None => {}
+                    },
+                }
+            }
+        }
+        sj::Value::Array(list) => {
+            if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64())
+                && (list.len() as u64) < min
+            {
+                violations.push(SchemaViolation {
+                    path: path.to_string(),
+                    msg: format!("Expected at least {min} items, found {}!", list.len()),
+                });
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64())
+                && (list.len() as u64) > max
+            {
+                violations.push(SchemaViolation {
+                    path: path.to_string(),
+                    msg: format!("Expected at most {max} items, found {}!", list.len()),
+                });
+            }
+            let prefix = schema
+                .get("prefixItems")
+                .and_then(|p| p.as_array())
+                .map(|p| p.as_slice())
+                .unwrap_or_default();
+            for (i, val) in list.iter().enumerate() {
+                let sub = prefix.get(i).or(schema.get("items"));
+                if let Some(sub) = sub {
+                    validate_in(root, sub, val, &format!("{path}/{i}"), violations);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::*,
+        combination::{StrucProto, attrs},
+        service::fas::FasFile,
+    };
+
+    #[test]
+    fn test_shipped_schema() {
+        let shipped: sj::Value =
+            sj::from_str(include_str!("../../schema/fas.schema.json")).unwrap();
+        assert_eq!(shipped, fas_schema(), "Schema file is out of date!");
+    }
+
+    #[test]
+    fn test_bounds() {
+        let schema = json!({
+            "type": "array",
+            "prefixItems": [
+                { "minimum": 0, "maximum": 1 },
+                { "exclusiveMinimum": 0, "exclusiveMaximum": 1 }
+            ]
+        });
+        assert!(validate(&schema, &json!([0, 0.5])).is_empty());
+        assert!(validate(&schema, &json!([1, 0.99])).is_empty());
+        let paths: Vec<String> = validate(&schema, &json!([1.5, 1]))
+            .into_iter()
+            .map(|v| v.path)
+            .collect();
+        assert_eq!(paths, ["/0", "/1"]);
+        assert_eq!(validate(&schema, &json!([-1, 0])).len(), 2);
+    }
+
+    #[test]
+    fn test_validate_fas() {
+        let mut file = FasFile::default();
+        let mut struc = StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]);
+        struc
+            .attrs
+            .set::<attrs::Allocs>(&DataHV::new(vec![2], vec![]));
+        struc.attrs.set::<attrs::CharBox>(&WorkBox::new(
+            WorkPoint::new(0.0, 0.0),
+            WorkPoint::new(0.5, 1.0),
+        ));
+        file.strucs.insert("一".to_string(), struc);
+
+        let mut value = sj::to_value(&file).unwrap();
+        assert_eq!(validate_fas(&value), vec![]);
+
+        value["strucs"]["一"]["attrs"]["alocs"] = json!({});
+        value["strucs"]["一"]["paths"][0]["kpoints"][1]["pos"] = json!([2, -1]);
+        value["config"]["interval"] = json!({"rules": [{"val": 1}]});
+        value["config"]["main_edge"] = json!([{
+            "conditions": { "not": { "section": "Top" }, "any": [{ "state_f": 1 }] },
+            "setup": [true, null]
+        }]);
+        value["config"]["smooth"] = json!({"tension": 1.5});
+        value["config"]["space_ctrls"] = json!({
+            "trend": { "scale": { "v": [{ "conditions": { "is_max": 1 } }] } },
+            "subarea": { "h": { "factor": "x" } },
+            "subcomp": { "section": [1, 1, 1] }
+        });
+        let paths: Vec<String> = validate_fas(&value).into_iter().map(|v| v.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/config/interval/rules/0",
+                "/config/interval/rules/0",
+                "/config/interval/rules/0",
+                "/config/interval/rules/0",
+                "/config/main_edge/0/conditions/any/0/state_f",
+                "/config/main_edge/0/conditions/not/section",
+                "/config/smooth/tension",
+                "/config/space_ctrls/subarea/h",
+                "/config/space_ctrls/trend/scale/v/0",
+                "/config/space_ctrls/trend/scale/v/0/conditions/is_max",
+                "/strucs/一/attrs/alocs",
+                "/strucs/一/paths/0/kpoints/1/pos/1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{base::*, config::SmoothSetting};
+
+fn is_corner(points: &[WorkPoint], i: usize, setting: &SmoothSetting) -> bool {
+    if i == 0 || i + 1 == points.len() {
+        return true;
+    }
+    let (v1, v2) = (points[i] - points[i - 1], points[i + 1] - points[i]);
+    let len = v1.length() * v2.length();
+    len == 0.0 || (v1.dot(v2) / len).clamp(-1.0, 1.0).acos().to_degrees() > setting.corner_angle
+}
+
+// Replaces the lines of a path with cubic curves through its on-curve points, with the tension
+// of its `stroke`. The ends, points labeled `CORNER` and turns sharper than `corner_angle` stay
+// corners, and paths that already have curves are kept.
+pub fn smooth_path(
+    kpoints: &[WorkKeyPoint],
+    setting: &SmoothSetting,
+    stroke: &str,
+) -> Vec<WorkKeyPoint> {
+    let tension = setting.tension(stroke);
+    if tension >= 1.0 || kpoints.iter().any(|kp| kp.is_off_curve()) {
+        return kpoints.to_vec();
+    }
+
+    let on_curve: Vec<&WorkKeyPoint> = kpoints.iter().filter(|kp| !kp.is_mark()).collect();
+    let points: Vec<WorkPoint> = on_curve.iter().map(|kp| kp.pos).collect();
+    let corners: Vec<bool> = on_curve
+        .iter()
+        .enumerate()
+        .map(|(i, kp)| kp.labels.iter().any(|l| l == CORNER) || is_corner(&points, i, setting))
+        .collect();
+
+    // Catmull-Rom tangents, one-sided along the line at corners.
+    let scale = 1.0 - tension;
+    let tangent = |i: usize, out: bool| -> WorkVec {
+        let t = match (corners[i], out) {
+            (true, true) => points[i + 1] - points[i],
+            (true, false) => points[i] - points[i - 1],
+            (false, _) => (points[i + 1] - points[i - 1]) / 2.0,
+        };
+        t * scale
+    };
+
+    let mut path = Vec::with_capacity(kpoints.len() + points.len() * 2);
+    let mut i = 0;
+    for kp in kpoints {
+        if kp.is_mark() {
+            path.push(kp.clone());
+            continue;
+        }
+        if i != 0 && !(corners[i - 1] && corners[i]) {
+            path.push(KeyPoint::off_curve(
+                points[i - 1] + tangent(i - 1, true) / 3.0,
+            ));
+            path.push(KeyPoint::off_curve(points[i] - tangent(i, false) / 3.0));
+        }
+        path.push(kp.clone());
+        i += 1;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combination::{CompData, StrucComb, StrucProto},
+        config::Config,
+    };
+    use serde_json as sj;
+
+    fn path(points: &[(f32, f32)]) -> Vec<WorkKeyPoint> {
+        points
+            .iter()
+            .map(|&(x, y)| WorkKeyPoint::new(WorkPoint::new(x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn test_smooth_path() {
+        let setting = SmoothSetting::default();
+        let bend = path(&[(0.0, 0.0), (1.0, 0.2), (2.0, 0.0)]);
+        let smooth = smooth_path(&bend, &setting, "");
+        let offs: Vec<bool> = smooth.iter().map(|kp| kp.is_off_curve()).collect();
+        assert_eq!(offs, [false, true, true, false, true, true, false]);
+        assert!((smooth[1].pos - WorkPoint::new(1.0 / 3.0, 0.2 / 3.0)).length() < 1e-5);
+        assert!((smooth[2].pos - WorkPoint::new(2.0 / 3.0, 0.2)).length() < 1e-5);
+        assert!((smooth[4].pos - WorkPoint::new(4.0 / 3.0, 0.2)).length() < 1e-5);
+
+        let mut corner = bend.clone();
+        corner[1].labels.push(CORNER.to_string());
+        assert_eq!(smooth_path(&corner, &setting, "").len(), 3);
+        let turn = path(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+        assert_eq!(smooth_path(&turn, &setting, "").len(), 3);
+        let straight = SmoothSetting {
+            tension: 1.0,
+            ..setting.clone()
+        };
+        assert_eq!(smooth_path(&bend, &straight, "").len(), 3);
+        let styled = SmoothSetting {
+            strokes: [("26".to_string(), 1.0)].into(),
+            ..setting.clone()
+        };
+        assert_eq!(smooth_path(&bend, &styled, "26").len(), 3);
+        assert_eq!(smooth_path(&bend, &styled, "").len(), 7);
+        assert_eq!(smooth_path(&smooth, &setting, "").len(), smooth.len());
+
+        let mut marked = bend.clone();
+        marked[1].labels.push("mark".to_string());
+        marked.insert(1, bend[1].clone());
+        let smooth = smooth_path(&marked, &setting, "");
+        assert_eq!(smooth.len(), 8);
+        assert!(smooth[4].is_mark());
+    }
+
+    #[test]
+    fn test_smooth_comp_tree() {
+        // 丿
+        let proto = StrucProto::from(vec![KeyPath::from([
+            key_pos(4, 0),
+            key_pos(3, 2),
+            key_pos(0, 4),
+        ])]);
+        let stroke = proto.strokes().remove(0);
+        let mut comb = StrucComb::new_single("丿".to_string(), proto);
+        if let CompData::Single { assigns, .. } = &mut comb.cdata {
+            *assigns = DataHV::new(
+                vec![AssignVal::new(0.3, 0.0), AssignVal::new(0.1, 0.0)],
+                vec![AssignVal::new(0.2, 0.0); 2],
+            );
+        }
+        comb.set_white_area(&DataHV::splat([0.1, 0.1]));
+
+        let config = Config::default();
+        let tree = comb.get_comp_tree(config.get_smooth());
+        assert!(tree.paths[0].iter().all(|kp| !kp.is_off_curve()));
+
+        let config: Config = sj::from_value(sj::json!({
+            "smooth": { "tension": 0.5, "strokes": { stroke: 1.0 } }
+        }))
+        .unwrap();
+        let tree = comb.get_comp_tree(config.get_smooth());
+        assert_eq!(tree.paths[0].len(), 3);
+
+        let config: Config = sj::from_value(sj::json!({ "smooth": { "tension": 0.5 } })).unwrap();
+        let tree = comb.get_comp_tree(config.get_smooth());
+        assert_eq!(tree.paths[0].len(), 7);
+        assert_eq!(
+            tree.paths[0].iter().filter(|kp| kp.is_off_curve()).count(),
+            4
+        );
+        let ink = tree.ink_box.unwrap();
+        let metrics = tree.metrics.unwrap();
+        assert!((metrics.bearings.h[0] - ink.min.x).abs() < 1e-5);
+        assert!(ink.max.x <= 0.5 + 1e-5 && ink.min.y >= 0.1 - 1e-5);
+    }
+}
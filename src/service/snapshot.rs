@@ -0,0 +1,274 @@
+use super::Service;
+
+use anyhow::{Result, anyhow};
+use serde_json as sj;
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+// Set to write the snapshots in `assert_snapshot` instead of comparing with them.
+pub const UPDATE_ENV: &str = "FASING_UPDATE_SNAPSHOTS";
+
+// The layout of each character: `{"info": StrucCombInfo, "tree": CompTree}` with the output
+// paths, or `{"error": message}` if it failed.
+pub type Snapshot = BTreeMap<char, sj::Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    // JSON pointer of the value in the snapshot of the character.
+    pub pointer: String,
+    pub old: Option<sj::Value>,
+    pub new: Option<sj::Value>,
+}
+
+#[derive(Debug, Default)]
+pub struct SnapshotDiff {
+    pub added: Vec<char>,
+    pub removed: Vec<char>,
+    pub changed: BTreeMap<char, Vec<Change>>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl std::fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |val: &Option<sj::Value>| match val {
+            Some(val) => val.to_string(),
+            None => "none".to_string(),
+        };
+
+        if !self.added.is_empty() {
+            writeln!(f, "added: {}", self.added.iter().collect::<String>())?;
+        }
+        if !self.removed.is_empty() {
+            writeln!(f, "removed: {}", self.removed.iter().collect::<String>())?;
+        }
+        if !self.changed.is_empty() {
+            writeln!(f, "changed: {}", self.changed.keys().collect::<String>())?;
+        }
+        for (chr, changes) in &self.changed {
+            writeln!(f, "{chr}:")?;
+            for change in changes {
+                writeln!(
+                    f,
+                    "  {}: {} -> {}",
+                    change.pointer,
+                    show(&change.old),
+                    show(&change.new)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn take_snapshot(service: &impl Service, chars: &[char]) -> Snapshot {
+    service
+        .gen_chars(chars)
+        .into_iter()
+        .map(|(chr, r)| {
+            let value = match r {
+                Ok(comb) => sj::json!({
+                    "info": comb.get_comb_info(),
+                    "tree": service.get_comp_tree(&comb),
+                }),
+                Err(e) => sj::json!({ "error": e.to_string() }),
+            };
+            (chr, value)
+        })
+        .collect()
+}
+
+fn file_name(chr: char) -> String {
+    format!("{:04X}.json", chr as u32)
+}
+
+// Writes a file per character, named by its code point like `6797.json`.
+pub fn save_snapshot(dir: &Path, snapshot: &Snapshot) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for (chr, value) in snapshot {
+        std::fs::write(dir.join(file_name(*chr)), sj::to_string_pretty(value)?)?;
+    }
+    Ok(())
+}
+
+pub fn load_snapshot(dir: &Path) -> Result<Snapshot> {
+    let mut snapshot = Snapshot::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let chr = path
+                .file_stem()
+                .and_then(|stem| u32::from_str_radix(&stem.to_string_lossy(), 16).ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| anyhow!("Invalid snapshot file `{}`!", path.display()))?;
+            snapshot.insert(chr, sj::from_str(&std::fs::read_to_string(&path)?)?);
+        }
+    }
+    Ok(snapshot)
+}
+
+fn compare_value(
+    pointer: String,
+    old: &sj::Value,
+    new: &sj::Value,
+    tolerance: f64,
+    changes: &mut Vec<Change>,
+) {
+    let changed = match (old, new) {
+        (sj::Value::Number(a), sj::Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => (a - b).abs() > tolerance,
+            _ => a != b,
+        },
+        (sj::Value::Array(a), sj::Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let pointer = format!("{pointer}/{i}");
+                match (a.get(i), b.get(i)) {
+                    (Some(a), Some(b)) => compare_value(pointer, a, b, tolerance, changes),
+                    (a, b) => changes.push(Change {
+                        pointer,
+                        old: a.cloned(),
+                        new: b.cloned(),
+                    }),
+                }
+            }
+            false
+        }
+        (sj::Value::Object(a), sj::Value::Object(b)) => {
+            let keys: std::collections::BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let pointer = format!("{pointer}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => compare_value(pointer, a, b, tolerance, changes),
+                    (a, b) => changes.push(Change {
+                        pointer,
+                        old: a.cloned(),
+                        new: b.cloned(),
+                    }),
+                }
+            }
+            false
+        }
+        _ => old != new,
+    };
+    if changed {
+        changes.push(Change {
+            pointer,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        });
+    }
+}
+
+// Numbers that differ by no more than `tolerance` are equal.
+pub fn compare_snapshot(old: &Snapshot, new: &Snapshot, tolerance: f64) -> SnapshotDiff {
+    let mut diff = SnapshotDiff::default();
+    for (chr, value) in new {
+        match old.get(chr) {
+            Some(old) => {
+                let mut changes = vec![];
+                compare_value(String::new(), old, value, tolerance, &mut changes);
+                if !changes.is_empty() {
+                    diff.changed.insert(*chr, changes);
+                }
+            }
+            None => diff.added.push(*chr),
+        }
+    }
+    diff.removed = old
+        .keys()
+        .filter(|chr| !new.contains_key(chr))
+        .copied()
+        .collect();
+    diff
+}
+
+// Test helper: compares the layout of `chars` with the snapshot in `dir` and panics with the
+// diff. The snapshot is only written if `FASING_UPDATE_SNAPSHOTS` is set.
+pub fn assert_snapshot(service: &impl Service, chars: &[char], dir: &Path, tolerance: f64) {
+    let snapshot = take_snapshot(service, chars);
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        save_snapshot(dir, &snapshot).unwrap();
+        return;
+    }
+
+    let old: Snapshot = load_snapshot(dir)
+        .unwrap_or_else(|e| {
+            panic!(
+                "Failed to load snapshot `{}`, set {UPDATE_ENV} to write it: {e}",
+                dir.display()
+            )
+        })
+        .into_iter()
+        .filter(|(chr, _)| chars.contains(chr))
+        .collect();
+    let diff = compare_snapshot(&old, &snapshot, tolerance);
+    if !diff.is_empty() {
+        let mut msg = format!("Snapshot `{}` changed!\n", dir.display());
+        write!(msg, "{diff}").unwrap();
+        panic!("{msg}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::test_util::stacked_service;
+
+    #[test]
+    fn test_compare_snapshot() {
+        let old = Snapshot::from([
+            ('一', sj::json!({ "a": [0.5, 0.25], "b": "x" })),
+            ('二', sj::json!({ "error": "e" })),
+        ]);
+        let new = Snapshot::from([
+            ('一', sj::json!({ "a": [0.50001, 0.3, 1.0], "b": "x" })),
+            ('三', sj::json!({ "error": "e" })),
+        ]);
+        let diff = compare_snapshot(&old, &new, 1e-4);
+        assert_eq!(diff.added, vec!['三']);
+        assert_eq!(diff.removed, vec!['二']);
+        assert_eq!(
+            diff.changed[&'一'],
+            vec![
+                Change {
+                    pointer: "/a/1".to_string(),
+                    old: Some(sj::json!(0.25)),
+                    new: Some(sj::json!(0.3)),
+                },
+                Change {
+                    pointer: "/a/2".to_string(),
+                    old: None,
+                    new: Some(sj::json!(1.0)),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "added: 三\nremoved: 二\nchanged: 一\n一:\n  /a/1: 0.25 -> 0.3\n  /a/2: none -> 1.0\n"
+        );
+        assert!(compare_snapshot(&new, &new, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut service = stacked_service();
+        let chars = ['一', '二', '三'];
+
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = take_snapshot(&service, &chars);
+        save_snapshot(dir.path(), &snapshot).unwrap();
+        let loaded = load_snapshot(dir.path()).unwrap();
+        assert!(compare_snapshot(&loaded, &snapshot, 1e-6).is_empty());
+        assert!(snapshot[&'三'].get("error").is_some());
+
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/snapshot");
+        assert_snapshot(&service, &chars, &fixtures, 1e-6);
+
+        service.config = sj::from_value(sj::json!({ "size": 0.8 })).unwrap();
+        let diff = compare_snapshot(&snapshot, &take_snapshot(&service, &chars), 1e-6);
+        assert_eq!(diff.changed.keys().collect::<String>(), "一二");
+    }
+}
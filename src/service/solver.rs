@@ -0,0 +1,746 @@
+use super::Service;
+use crate::{
+    base::*,
+    combination::{CompData, StrucComb},
+};
+
+const EPSILON: f64 = 1e-9;
+const TOLERANCE: f64 = 1e-6;
+
+// Minimizes `weight * (x - target)^2` with `x >= min`.
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub name: String,
+    pub min: f32,
+    pub target: f32,
+    pub weight: f32,
+}
+
+// `sum(coefficient * x) == value`
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub name: String,
+    pub terms: Vec<(usize, f32)>,
+    pub value: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolveError {
+    Infeasible {
+        constraint: String,
+        need: f32,
+        value: f32,
+    },
+    Inconsistent {
+        constraint: String,
+        residual: f32,
+    },
+    Unconverged {
+        iterations: usize,
+    },
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Infeasible {
+                constraint,
+                need,
+                value,
+            } => write!(
+                f,
+                "`{}` needs at least {} but only {} is available",
+                constraint, need, value
+            ),
+            Self::Inconsistent {
+                constraint,
+                residual,
+            } => write!(
+                f,
+                "`{}` cannot be satisfied, off by {}",
+                constraint, residual
+            ),
+            Self::Unconverged { iterations } => {
+                write!(f, "No solution found in {} iterations", iterations)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct Problem {
+    pub vars: Vec<Variable>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl Problem {
+    pub fn var(&mut self, name: impl Into<String>, min: f32, target: f32, weight: f32) -> usize {
+        self.vars.push(Variable {
+            name: name.into(),
+            min,
+            target,
+            weight,
+        });
+        self.vars.len() - 1
+    }
+
+    pub fn constrain(&mut self, name: impl Into<String>, terms: Vec<(usize, f32)>, value: f32) {
+        self.constraints.push(Constraint {
+            name: name.into(),
+            terms,
+            value,
+        });
+    }
+
+    // Active set method: the variables at their minimum are fixed, the rest are solved with the
+    // constraints as a least squares problem, until no variable wants to leave or enter the set.
+    pub fn solve(&self) -> Result<Vec<f32>, SolveError> {
+        for c in &self.constraints {
+            if c.terms.iter().all(|(_, a)| *a >= 0.0) {
+                let need: f32 = c.terms.iter().map(|&(i, a)| a * self.vars[i].min).sum();
+                if need as f64 > c.value as f64 + TOLERANCE {
+                    return Err(SolveError::Infeasible {
+                        constraint: c.name.clone(),
+                        need,
+                        value: c.value,
+                    });
+                }
+            }
+        }
+
+        let n = self.vars.len();
+        let min: Vec<f64> = self.vars.iter().map(|v| v.min as f64).collect();
+        let target: Vec<f64> = self.vars.iter().map(|v| v.target as f64).collect();
+        let weight: Vec<f64> = self
+            .vars
+            .iter()
+            .map(|v| (v.weight as f64).max(EPSILON))
+            .collect();
+        let mut fixed = vec![false; n];
+        let iterations = 4 * n + 4;
+        let mut result = Err(SolveError::Unconverged { iterations });
+
+        for _ in 0..iterations {
+            let (lambda, bad_row) = self.multipliers(&fixed, &min, &target, &weight);
+            let at = |j: usize| -> f64 {
+                self.constraints
+                    .iter()
+                    .zip(&lambda)
+                    .flat_map(|(c, l)| {
+                        c.terms
+                            .iter()
+                            .filter(|t| t.0 == j)
+                            .map(move |t| t.1 as f64 * l)
+                    })
+                    .sum()
+            };
+
+            if let Some((row, residual)) = bad_row {
+                result = Err(SolveError::Inconsistent {
+                    constraint: self.constraints[row].name.clone(),
+                    residual: residual as f32,
+                });
+                let release: Vec<usize> = self.constraints[row]
+                    .terms
+                    .iter()
+                    .filter(|(i, a)| fixed[*i] && *a != 0.0)
+                    .map(|(i, _)| *i)
+                    .collect();
+                if release.is_empty() {
+                    break;
+                }
+                release.into_iter().for_each(|i| fixed[i] = false);
+                continue;
+            }
+
+            let x: Vec<f64> = (0..n)
+                .map(|j| match fixed[j] {
+                    true => min[j],
+                    false => target[j] + at(j) / weight[j],
+                })
+                .collect();
+
+            let violators: Vec<usize> = (0..n)
+                .filter(|&j| !fixed[j] && x[j] < min[j] - TOLERANCE)
+                .collect();
+            if !violators.is_empty() {
+                violators.into_iter().for_each(|j| fixed[j] = true);
+                continue;
+            }
+
+            let release = (0..n)
+                .filter(|&j| fixed[j])
+                .map(|j| (j, weight[j] * (min[j] - target[j]) - at(j)))
+                .filter(|(_, mu)| *mu < -TOLERANCE)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            match release {
+                Some((j, _)) => fixed[j] = false,
+                None => {
+                    result = Ok(x.iter().zip(&min).map(|(x, m)| x.max(*m) as f32).collect());
+                    break;
+                }
+            }
+        }
+
+        result
+    }
+
+    // Solves `A W^-1 A^T lambda = b - A x0` over the free variables, with `x0` being the targets
+    // of the free ones and the minimums of the fixed ones.
+    fn multipliers(
+        &self,
+        fixed: &[bool],
+        min: &[f64],
+        target: &[f64],
+        weight: &[f64],
+    ) -> (Vec<f64>, Option<(usize, f64)>) {
+        let m = self.constraints.len();
+        let mut mat = vec![vec![0.0; m]; m];
+        let mut rhs: Vec<f64> = self
+            .constraints
+            .iter()
+            .map(|c| {
+                c.value as f64
+                    - c.terms
+                        .iter()
+                        .map(|&(i, a)| {
+                            a as f64
+                                * match fixed[i] {
+                                    true => min[i],
+                                    false => target[i],
+                                }
+                        })
+                        .sum::<f64>()
+            })
+            .collect();
+        for (r1, c1) in self.constraints.iter().enumerate() {
+            for (r2, c2) in self.constraints.iter().enumerate() {
+                mat[r1][r2] = c1
+                    .terms
+                    .iter()
+                    .filter(|(i, _)| !fixed[*i])
+                    .map(|&(i, a1)| {
+                        c2.terms
+                            .iter()
+                            .filter(|t| t.0 == i)
+                            .map(|t| a1 as f64 * t.1 as f64 / weight[i])
+                            .sum::<f64>()
+                    })
+                    .sum();
+            }
+        }
+
+        // Gaussian elimination, skipping the dependent rows.
+        let mut rows: Vec<usize> = (0..m).collect();
+        let mut pivots = vec![];
+        let mut r = 0;
+        for col in 0..m {
+            let Some(p) = (r..m).max_by(|&a, &b| {
+                mat[rows[a]][col]
+                    .abs()
+                    .partial_cmp(&mat[rows[b]][col].abs())
+                    .unwrap()
+            }) else {
+                break;
+            };
+            if mat[rows[p]][col].abs() < EPSILON {
+                continue;
+            }
+            rows.swap(r, p);
+            let pr = rows[r];
+            for &row in rows.iter().skip(r + 1) {
+                let f = mat[row][col] / mat[pr][col];
+                if f != 0.0 {
+                    let pivot_row = mat[pr].clone();
+                    mat[row]
+                        .iter_mut()
+                        .zip(pivot_row)
+                        .skip(col)
+                        .for_each(|(v, p)| *v -= f * p);
+                    rhs[row] -= f * rhs[pr];
+                }
+            }
+            pivots.push((pr, col));
+            r += 1;
+        }
+
+        let bad_row = rows[r..]
+            .iter()
+            .map(|&row| (row, rhs[row]))
+            .find(|(_, res)| res.abs() > TOLERANCE);
+
+        let mut lambda = vec![0.0; m];
+        for &(row, col) in pivots.iter().rev() {
+            let sum: f64 = (col + 1..m).map(|k| mat[row][k] * lambda[k]).sum();
+            lambda[col] = (rhs[row] - sum) / mat[row][col];
+        }
+
+        (lambda, bad_row)
+    }
+}
+
+enum Slot {
+    White(usize),
+    Blank(String, usize),
+    Assign(String, usize),
+    Interval(String, usize),
+}
+
+struct Builder<'a, S: Service> {
+    service: &'a S,
+    axis: Axis,
+    // Target length of a base unit in each axis.
+    scales: DataHV<f32>,
+    min_unit: f32,
+    white_unit: f32,
+    problem: Problem,
+    slots: Vec<Slot>,
+}
+
+impl<'a, S: Service> Builder<'a, S> {
+    fn var(&mut self, name: String, min: f32, target: f32, slot: Slot) -> (usize, f32) {
+        self.slots.push(slot);
+        (self.problem.var(name, min, target.max(min), 1.0), 1.0)
+    }
+
+    // Returns the terms of the node's total length, blanks included.
+    fn node(&mut self, comb: &StrucComb, path: &str) -> Vec<(usize, f32)> {
+        let axis = self.axis;
+        let scale = *self.scales.hv_get(axis);
+        let cfg = self.service.get_config();
+        let mut terms = vec![];
+
+        match &comb.cdata {
+            CompData::Single { proto, .. } => {
+                let allocs = proto.allocation_space();
+                let size = self
+                    .scales
+                    .zip(
+                        allocs
+                            .as_ref()
+                            .map(|list| list.iter().sum::<usize>() as f32),
+                    )
+                    .into_map(|(scale, total)| scale * total);
+                let weights = proto.subarea_weight(size);
+                let (allocs, weights) = (allocs.hv_get(axis), weights.hv_get(axis));
+                let total = allocs.iter().sum::<usize>() as f32;
+                let weight_sum = weights.iter().sum::<f32>();
+
+                for (i, &n) in allocs.iter().enumerate().filter(|(_, n)| **n != 0) {
+                    let target = match weights.len() == allocs.len() && weight_sum != 0.0 {
+                        true => scale * total * weights[i] / weight_sum,
+                        false => scale * n as f32,
+                    };
+                    let name = format!("{}[{i}]", comb.name);
+                    let min = n as f32 * self.min_unit;
+                    terms.push(self.var(name, min, target, Slot::Assign(path.to_string(), i)));
+                }
+            }
+            CompData::Scale {
+                axis: c_axis,
+                comps,
+                intervals,
+                ..
+            } => {
+                let children: Vec<_> = comps
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (c, self.node(c, &StrucComb::child_path(path, i))))
+                    .collect();
+
+                if *c_axis == axis {
+                    let limit = cfg.get_interval_limit(axis).unwrap_or(1.0);
+                    children.into_iter().for_each(|(_, t)| terms.extend(t));
+                    for (i, &len) in intervals.iter().enumerate().filter(|(_, l)| **l != 0) {
+                        let name = format!("{}/interval[{i}]", comb.name);
+                        let (min, target) =
+                            (len as f32 * self.min_unit, scale * len as f32 * limit);
+                        terms.push(self.var(
+                            name,
+                            min,
+                            target,
+                            Slot::Interval(path.to_string(), i),
+                        ));
+                    }
+                } else {
+                    let mut iter = children.into_iter().filter(|(_, t)| !t.is_empty());
+                    if let Some((first, t0)) = iter.next() {
+                        for (c, t) in iter {
+                            let mut eq = t;
+                            eq.extend(t0.iter().map(|&(i, a)| (i, -a)));
+                            self.problem
+                                .constrain(format!("{} = {}", c.name, first.name), eq, 0.0);
+                        }
+                        terms = t0;
+                    }
+                }
+            }
+            CompData::Surround { .. } => todo!(), // surround
+        }
+
+        let white = cfg.get_space_assign_settings();
+        let sharpness = cfg.get_sharpness(axis);
+        for side in Side::fb() {
+            let i = side.n();
+            if comb.blanks.hv_get(axis)[i].base == 0.0 {
+                continue;
+            }
+            let vcorr = (1.0 - comb.get_edge(axis, side, false).sharpness(sharpness))
+                * white.1.hv_get(axis)[i];
+            let target = scale * white.0.hv_get(axis)[i] - vcorr;
+            let name = format!("{}/blank[{i}]", comb.name);
+            let slot = Slot::Blank(path.to_string(), i);
+            terms.push(self.var(name, self.white_unit, target, slot));
+        }
+        terms
+    }
+}
+
+// The character face of an axis: its size, the base length and the targets of the white areas.
+struct Frame {
+    size: f32,
+    base_len: usize,
+    white: [f32; 2],
+    min_white: f32,
+}
+
+impl Frame {
+    fn new(service: &impl Service, comb: &StrucComb, axis: Axis) -> Self {
+        let cfg = service.get_config();
+        let size = cfg.size.hv_get(axis) * comb.get_char_box().size().to_hv_data().hv_get(axis);
+        let base_len = comb.get_bases_length(axis, true);
+        let zimian = cfg.zimian.hv_get(axis);
+        let min_white = ((cfg.size.hv_get(axis) - zimian.max_val()) / 2.0).max(0.0);
+        if base_len == 0 {
+            return Self {
+                size,
+                base_len,
+                white: [size / 2.0; 2],
+                min_white,
+            };
+        }
+
+        let white = (size - zimian.val_in(base_len)) / 2.0;
+        let sharpness = cfg.get_sharpness(axis);
+        let white = Side::fb().map(|side| {
+            let vcorr = (1.0 - comb.get_edge(axis, side, false).sharpness(sharpness))
+                * cfg.get_visual_corr(axis);
+            white - vcorr.min(white - min_white)
+        });
+        Self {
+            size,
+            base_len,
+            white,
+            min_white,
+        }
+    }
+
+    fn scale(&self) -> f32 {
+        match self.base_len {
+            0 => 0.0,
+            n => (self.size - self.white[0] - self.white[1]) / n as f32,
+        }
+    }
+}
+
+// The solved space of an axis, written to the combination by `apply`.
+pub struct AxisSpace {
+    pub axis: Axis,
+    pub level: usize,
+    pub base_len: usize,
+    pub length: f32,
+    pub white: [f32; 2],
+    slots: Vec<(Slot, AssignVal)>,
+}
+
+impl AxisSpace {
+    // Sets every value of the axis and returns the white areas.
+    pub fn apply(self, comb: &mut StrucComb) -> [f32; 2] {
+        fn reset(comb: &mut StrucComb, axis: Axis, level: usize) {
+            comb.blanks
+                .hv_get_mut(axis)
+                .iter_mut()
+                .for_each(|b| *b = AssignVal::new(b.base, 0.0));
+            match &mut comb.cdata {
+                CompData::Single {
+                    proto,
+                    assigns,
+                    level: c_level,
+                    ..
+                } => {
+                    let len = proto.allocation_space().hv_get(axis).len();
+                    *assigns.hv_get_mut(axis) = vec![Default::default(); len];
+                    *c_level.hv_get_mut(axis) = level;
+                }
+                CompData::Scale {
+                    axis: c_axis,
+                    comps,
+                    intervals,
+                    intervals_val,
+                } => {
+                    if *c_axis == axis {
+                        *intervals_val = vec![Default::default(); intervals.len()];
+                    }
+                    comps.iter_mut().for_each(|c| reset(c, axis, level));
+                }
+                CompData::Surround { .. } => todo!(), // surround
+            }
+        }
+
+        let axis = self.axis;
+        reset(comb, axis, self.level);
+        let mut white = self.white;
+        for (slot, val) in self.slots {
+            match slot {
+                Slot::White(i) => white[i] = val.total(),
+                Slot::Blank(path, i) => {
+                    comb.node_mut(&path).unwrap().blanks.hv_get_mut(axis)[i] = val
+                }
+                Slot::Assign(path, i) => {
+                    if let CompData::Single { assigns, .. } =
+                        &mut comb.node_mut(&path).unwrap().cdata
+                    {
+                        assigns.hv_get_mut(axis)[i] = val
+                    }
+                }
+                Slot::Interval(path, i) => {
+                    if let CompData::Scale { intervals_val, .. } =
+                        &mut comb.node_mut(&path).unwrap().cdata
+                    {
+                        intervals_val[i] = val
+                    }
+                }
+            }
+        }
+        white
+    }
+}
+
+// Solves the space of an axis from the config: every value is at least its bases in the smallest
+// unit, the white areas keep the margin of `zimian`, and all are drawn to the targets of
+// `zimian`, `space_assign` and the subarea weights.
+pub fn solve_axis(
+    service: &impl Service,
+    comb: &StrucComb,
+    axis: Axis,
+) -> Result<AxisSpace, SolveError> {
+    let cfg = service.get_config();
+    let units = cfg.units.hv_get(axis);
+    let frame = Frame::new(service, comb, axis);
+    let mut space = AxisSpace {
+        axis,
+        level: 0,
+        base_len: frame.base_len,
+        length: 0.0,
+        white: frame.white,
+        slots: vec![],
+    };
+    if frame.base_len == 0 {
+        return Ok(space);
+    }
+
+    let min_unit = units.iter().copied().reduce(f32::min).unwrap_or_default();
+    let mut builder = Builder {
+        service,
+        axis,
+        scales: Axis::hv().into_map(|axis| Frame::new(service, comb, axis).scale()),
+        min_unit,
+        white_unit: cfg
+            .get_space_assign_settings()
+            .2
+            .hv_get(axis)
+            .map_or(min_unit, |unit| unit.min(min_unit)),
+        problem: Problem::default(),
+        slots: vec![],
+    };
+    let mut terms = builder.node(comb, "");
+    for (i, target) in frame.white.into_iter().enumerate() {
+        let name = format!("{}/white[{i}]", comb.name);
+        terms.push(builder.var(name, frame.min_white, target, Slot::White(i)));
+    }
+    builder
+        .problem
+        .constrain(format!("{} total", comb.name), terms, frame.size);
+
+    let values = builder.problem.solve()?;
+    space.slots = builder
+        .slots
+        .into_iter()
+        .zip(builder.problem.vars.iter().zip(values))
+        .map(|(slot, (var, x))| (slot, AssignVal::new(var.min, x - var.min)))
+        .collect();
+    space.length = frame.size
+        - space
+            .slots
+            .iter()
+            .filter(|(slot, _)| matches!(slot, Slot::White(_)))
+            .map(|(_, val)| val.total())
+            .sum::<f32>();
+    let scale = space.length / frame.base_len as f32;
+    space.level = units
+        .iter()
+        .position(|&unit| scale >= unit)
+        .unwrap_or(units.len().saturating_sub(1));
+    Ok(space)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combination::StrucProto,
+        config::Config,
+        construct::{CpAttrs, CstError, CstTable},
+        service::SimpleService,
+    };
+    use serde_json as sj;
+    use std::{cell::RefCell, rc::Rc};
+
+    fn assert_near(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut problem = Problem::default();
+        let a = problem.var("a", 0.1, 0.3, 1.0);
+        let b = problem.var("b", 0.1, 0.3, 1.0);
+        problem.constrain("sum", vec![(a, 1.0), (b, 1.0)], 1.0);
+        let x = problem.solve().unwrap();
+        assert_near(x[a], 0.5);
+        assert_near(x[b], 0.5);
+
+        let mut problem = Problem::default();
+        let a = problem.var("a", 0.0, 0.8, 1.0);
+        let b = problem.var("b", 0.45, 0.0, 1.0);
+        let c = problem.var("c", 0.0, 0.2, 1.0);
+        let d = problem.var("d", 0.0, 0.6, 1.0);
+        problem.constrain("ab", vec![(a, 1.0), (b, 1.0)], 1.0);
+        problem.constrain("c = a", vec![(c, 1.0), (a, -1.0)], 0.0);
+        problem.constrain("cd", vec![(c, 1.0), (d, 1.0)], 1.0);
+        let x = problem.solve().unwrap();
+        assert_near(x[b], 0.45);
+        assert_near(x[a], 0.55);
+        assert_near(x[c], 0.55);
+        assert_near(x[d], 0.45);
+
+        let mut problem = Problem::default();
+        let a = problem.var("a", 0.6, 0.5, 1.0);
+        let b = problem.var("b", 0.6, 0.5, 1.0);
+        problem.constrain("sum", vec![(a, 1.0), (b, 1.0)], 1.0);
+        assert!(matches!(
+            problem.solve(),
+            Err(SolveError::Infeasible { constraint, .. }) if constraint == "sum"
+        ));
+
+        let mut problem = Problem::default();
+        let a = problem.var("a", 0.2, 0.5, 1.0);
+        let b = problem.var("b", 0.0, 0.5, 1.0);
+        problem.constrain("a = b", vec![(a, 1.0), (b, -1.0)], 0.0);
+        problem.constrain("a", vec![(a, 1.0)], 0.1);
+        assert!(problem.solve().is_err());
+    }
+
+    #[test]
+    fn test_solve_space() {
+        let mut table = CstTable::empty();
+        for name in ["一", "口"] {
+            table.insert(name.to_string(), CpAttrs::single());
+        }
+        table.insert(
+            "二".to_string(),
+            sj::from_value(sj::json!({ "tp": "⿰", "components": ["一", "口"] })).unwrap(),
+        );
+
+        let mut service = SimpleService::new(table);
+        service.strucs.insert(
+            "一".to_string(),
+            StrucProto::from(vec![KeyPath::from([key_pos(0, 1), key_pos(2, 1)])]),
+        );
+        service.strucs.insert(
+            "口".to_string(),
+            StrucProto::from(vec![KeyPath::from([
+                key_pos(0, 0),
+                key_pos(2, 0),
+                key_pos(2, 2),
+                key_pos(0, 2),
+                key_pos(0, 0),
+            ])]),
+        );
+
+        let greedy = service
+            .get_struc_comb(service.get_char_tree("二".to_string()))
+            .unwrap();
+        service.config =
+            sj::from_value(sj::json!({ "space_assign": { "mode": "Solver" } })).unwrap();
+        let solved = service
+            .get_struc_comb(service.get_char_tree("二".to_string()))
+            .unwrap();
+
+        for axis in Axis::list() {
+            assert_near(
+                solved.get_assign_value(axis, true).total(),
+                greedy.get_assign_value(axis, true).total(),
+            );
+        }
+        let (c1, c2) = (solved.node("0").unwrap(), solved.node("1").unwrap());
+        assert_near(
+            c1.get_assign_value(Axis::Horizontal, true).total(),
+            c2.get_assign_value(Axis::Horizontal, true).total(),
+        );
+        assert_near(
+            c2.get_assign_value(Axis::Vertical, true).total(),
+            solved.get_assign_value(Axis::Vertical, false).total(),
+        );
+
+        // 0.6 of bases do not fit in the zimian length of 0.58, the solver takes it from the
+        // white area down to the margin of 0.05.
+        let config = |mode: &str, unit: f32| -> Config {
+            sj::from_value(sj::json!({
+                "units": [unit],
+                "zimian": [[0, 0.5], [10, 0.9]],
+                "space_assign": { "mode": mode }
+            }))
+            .unwrap()
+        };
+        let gen_kou = |service: &SimpleService| {
+            service.get_struc_comb(service.get_char_tree("口".to_string()))
+        };
+        service.config = config("Greedy", 0.3);
+        assert!(matches!(
+            gen_kou(&service),
+            Err(CstError::AxisTransform { .. })
+        ));
+        service.config = config("Solver", 0.3);
+        let kou = gen_kou(&service).unwrap();
+        let white = kou.get_white_area().unwrap();
+        for axis in Axis::list() {
+            assert_near(kou.get_assign_value(axis, false).total(), 0.6);
+            assert_near(white.hv_get(axis)[0], 0.2);
+            assert_near(white.hv_get(axis)[1], 0.2);
+        }
+        service.config = config("Solver", 0.5);
+        assert!(matches!(
+            gen_kou(&service),
+            Err(CstError::Infeasible { .. })
+        ));
+
+        // The space controls still run on the solved comb.
+        service.config = sj::from_value(sj::json!({
+            "space_assign": { "mode": "Solver" },
+            "space_ctrls": { "order": ["mark"], "mark": {} }
+        }))
+        .unwrap();
+        let calls = Rc::new(RefCell::new(vec![]));
+        let log = calls.clone();
+        service
+            .ctrls
+            .register("mark", move |comb: &mut StrucComb, _: &sj::Value| {
+                log.borrow_mut().push(comb.name.clone())
+            });
+        gen_kou(&service).unwrap();
+        assert_eq!(*calls.borrow(), vec!["口".to_string()]);
+    }
+}
@@ -1,4 +1,5 @@
 use super::algorithm as al;
+use super::trace::{TraceEvent, Tracer};
 use crate::{
     base::*,
     combination::{CompData, StrucComb, attrs, view},
@@ -103,7 +104,240 @@ pub fn ctrl_subcomp(comb: &mut StrucComb, value: &sj::Value) {
     }
 }
 
-pub fn ctrl_trend(comb: &mut StrucComb, value: &sj::Value) {
+// Gives the components along the scale axis lengths in proportion to their ink, so that the
+// gray of each gets closer. `stroke` is the width of horizontal and vertical strokes.
+pub fn ctrl_gray(comb: &mut StrucComb, value: &sj::Value) {
+    match &mut comb.cdata {
+        CompData::Single { .. } => {}
+        CompData::Scale { comps, axis, .. } => {
+            let stroke = value
+                .get("stroke")
+                .map(|val| {
+                    config::get_axis_val(val)
+                        .into_map(|v| v.and_then(|v| v.as_f64()).unwrap_or(1.0) as f32)
+                })
+                .unwrap_or(DataHV::splat(1.0));
+            let factor = value
+                .get("factor")
+                .and_then(|val| *config::get_axis_val(val).hv_get(*axis))
+                .and_then(|val| val.as_f64())
+                .unwrap_or(1.0) as f32;
+
+            let axis = *axis;
+            let inks: Vec<f32> = comps
+                .iter()
+                .map(|c| match c.get_bases_length(axis, true) {
+                    0 => 0.0,
+                    _ => c.get_ink_area(stroke),
+                })
+                .collect();
+            let mut comp_assigns: Vec<AssignVal> = comps
+                .iter()
+                .map(|c| c.get_assign_value(axis, true))
+                .collect();
+
+            al::reallocate_on_weights(&mut comp_assigns, &inks, factor);
+            comps.iter_mut().zip(comp_assigns).for_each(|(c, v)| {
+                c.reassign_space(v.total(), true, axis);
+                ctrl_gray(c, value);
+            });
+        }
+        CompData::Surround { .. } => todo!(), // surround
+    }
+}
+
+// An alloc boundary holding strokes that run across the axis.
+struct Line {
+    // Path of the single component from the aligned one.
+    path: String,
+    k: usize,
+    // Position from the front of the aligned component.
+    pos: f32,
+}
+
+// The lines of all the single components, nested ones at their offset in `comb`.
+fn stroke_lines(comb: &StrucComb, axis: Axis) -> Vec<Line> {
+    let mut lines = vec![];
+    stroke_lines_in(comb, axis, String::new(), 0.0, &mut lines);
+    lines
+}
+
+fn stroke_lines_in(comb: &StrucComb, axis: Axis, path: String, start: f32, lines: &mut Vec<Line>) {
+    let start = start + comb.blanks.hv_get(axis)[0].total();
+    match &comb.cdata {
+        CompData::Single { proto, assigns, .. } => {
+            let values_map = proto.values_map(true);
+            let values_map = values_map.hv_get(axis);
+            let mut bounds: Vec<usize> = proto
+                .paths
+                .iter()
+                .filter(|path| !path.hide)
+                .flat_map(|path| {
+                    let kpoints: Vec<WorkKeyPoint> = path
+                        .kpoints
+                        .iter()
+                        .map(|kp| KeyPoint {
+                            pos: kp.pos.cast().cast_unit(),
+                            labels: kp.labels.clone(),
+                        })
+                        .collect();
+                    // Only straight lines on the grid, a curve is flat only if it is degenerate.
+                    flatten_curves(&kpoints, CURVE_STEPS)
+                        .windows(2)
+                        .filter(|p| {
+                            p[0].hv_get(axis) == p[1].hv_get(axis)
+                                && p[0].hv_get(axis.inverse()) != p[1].hv_get(axis.inverse())
+                                && p[0].hv_get(axis).fract() == 0.0
+                        })
+                        .filter_map(|p| values_map.get(&(*p[0].hv_get(axis) as usize)).copied())
+                        .collect::<Vec<usize>>()
+                })
+                .collect();
+            bounds.sort();
+            bounds.dedup();
+
+            let assigns = assigns.hv_get(axis);
+            lines.extend(bounds.into_iter().map(|k| Line {
+                path: path.clone(),
+                k,
+                pos: start + assigns[..k].iter().map(|v| v.total()).sum::<f32>(),
+            }))
+        }
+        CompData::Scale {
+            axis: c_axis,
+            comps,
+            intervals_val,
+            ..
+        } => {
+            let mut offset = start;
+            for (i, c) in comps.iter().enumerate() {
+                stroke_lines_in(c, axis, StrucComb::child_path(&path, i), offset, lines);
+                if *c_axis == axis {
+                    offset += c.get_assign_value(axis, true).total()
+                        + intervals_val.get(i).map_or(0.0, |v| v.total());
+                }
+            }
+        }
+        CompData::Surround { .. } => {}
+    }
+}
+
+// Moves the line by `delta`, keeping the total length of its component.
+fn shift_line(comb: &mut StrucComb, axis: Axis, line: &Line, delta: f32, check: bool) -> bool {
+    let k = line.k;
+    match comb.node_mut(&line.path).map(|c| &mut c.cdata) {
+        Some(CompData::Single { assigns, .. }) => {
+            let assigns = assigns.hv_get_mut(axis);
+            if k == 0 || k >= assigns.len() {
+                return delta == 0.0;
+            }
+            let (front, back) = (assigns[k - 1], assigns[k]);
+            let ok = front.total() + delta >= front.base - al::NORMAL_OFFSET
+                && back.total() - delta >= back.base - al::NORMAL_OFFSET;
+            if ok && !check {
+                assigns[k - 1].uncheck_set(front.total() + delta);
+                assigns[k].uncheck_set(back.total() - delta);
+            }
+            ok
+        }
+        _ => false,
+    }
+}
+
+fn is_inner_line(comb: &StrucComb, axis: Axis, line: &Line) -> bool {
+    match comb.node(&line.path).map(|c| &c.cdata) {
+        Some(CompData::Single { assigns, .. }) => {
+            line.k != 0 && line.k < assigns.hv_get(axis).len()
+        }
+        _ => false,
+    }
+}
+
+// Snaps the strokes of sibling components that are within `tolerance` of each other to a shared
+// position, e.g. the horizontals of the two `木` in `林`.
+pub fn ctrl_align(comb: &mut StrucComb, value: &sj::Value) {
+    match &mut comb.cdata {
+        CompData::Single { .. } => {}
+        CompData::Scale { comps, axis, .. } => {
+            let axis = axis.inverse();
+            let tolerance = value
+                .get("tolerance")
+                .and_then(|val| *config::get_axis_val(val).hv_get(axis))
+                .and_then(|val| val.as_f64())
+                .unwrap_or(0.02) as f32;
+
+            let mut lines: Vec<(usize, Line)> = comps
+                .iter()
+                .enumerate()
+                .flat_map(|(i, c)| stroke_lines(c, axis).into_iter().map(move |l| (i, l)))
+                .collect();
+            lines.sort_by(|a, b| a.1.pos.total_cmp(&b.1.pos));
+
+            // Clusters of lines spanning at most `tolerance`, in order of position.
+            let mut clusters: Vec<Vec<(usize, Line)>> = vec![];
+            for line in lines {
+                match clusters.last_mut() {
+                    Some(cluster) if line.1.pos - cluster[0].1.pos <= tolerance => {
+                        cluster.push(line)
+                    }
+                    _ => clusters.push(vec![line]),
+                }
+            }
+
+            for cluster in clusters {
+                let first = cluster[0].0;
+                if cluster.iter().all(|(i, _)| *i == first) {
+                    continue;
+                }
+                let (inner, outer): (Vec<_>, Vec<_>) = cluster
+                    .iter()
+                    .partition(|(i, l)| is_inner_line(&comps[*i], axis, l));
+                // Lines at the edge of a component stay, the others follow them.
+                let target = match outer.first() {
+                    Some((_, l)) => {
+                        if outer
+                            .iter()
+                            .any(|o| (o.1.pos - l.pos).abs() > al::NORMAL_OFFSET)
+                        {
+                            continue;
+                        }
+                        l.pos
+                    }
+                    None => inner.iter().map(|(_, l)| l.pos).sum::<f32>() / inner.len() as f32,
+                };
+                if inner
+                    .iter()
+                    .all(|(_, l)| (l.pos - target).abs() < al::NORMAL_OFFSET)
+                {
+                    continue;
+                }
+                // Two lines of a component would close the space between them.
+                if inner.iter().enumerate().any(|(n, (i, l))| {
+                    inner[..n]
+                        .iter()
+                        .any(|(j, other)| i == j && l.path == other.path)
+                }) {
+                    continue;
+                }
+
+                // Moving a line leaves the others of the component in place.
+                if inner
+                    .iter()
+                    .all(|(i, l)| shift_line(&mut comps[*i], axis, l, target - l.pos, true))
+                {
+                    for (i, l) in inner {
+                        shift_line(&mut comps[*i], axis, l, target - l.pos, false);
+                    }
+                }
+            }
+
+            comps.iter_mut().for_each(|c| ctrl_align(c, value));
+        }
+        CompData::Surround { .. } => todo!(), // surround
+    }
+}
+
+pub fn ctrl_trend(comb: &mut StrucComb, value: &sj::Value, tracer: &mut Tracer) {
     match &mut comb.cdata {
         CompData::Single { .. } => {}
         CompData::Scale { comps, axis, .. } => {
@@ -134,9 +368,11 @@ pub fn ctrl_trend(comb: &mut StrucComb, value: &sj::Value) {
                 .map(|val| sj::from_value::<Vec<config::EdgeCheck<f32>>>(val.clone()))
             {
                 Some(Ok(settings)) => {
+                    let order = config::priority_order(&settings);
                     for i in 0..comps.len() {
                         let blanks = comps[i].get_blank_base(axis);
-                        for tcheck in settings.iter() {
+                        for &j in order.iter() {
+                            let tcheck = &settings[j];
                             if blanks
                                 .iter()
                                 .zip(tcheck.setup.iter())
@@ -156,6 +392,9 @@ pub fn ctrl_trend(comb: &mut StrucComb, value: &sj::Value) {
                             match r {
                                 Ok(r) => {
                                     if r {
+                                        tracer.record(&i.to_string(), &comps[i].name, || {
+                                            TraceEvent::Trend { axis, rule: j }
+                                        });
                                         let c_assign = comps[i].get_assign_value(axis, false);
                                         let blanks = comps[i].blanks.hv_get_mut(axis);
                                         let mut assign_list: Vec<_> = blanks
@@ -193,7 +432,9 @@ pub fn ctrl_trend(comb: &mut StrucComb, value: &sj::Value) {
                 None => {}
             }
 
-            comps.iter_mut().for_each(|c| ctrl_trend(c, value));
+            for (i, c) in comps.iter_mut().enumerate() {
+                tracer.within(&i.to_string(), |tracer| ctrl_trend(c, value, tracer));
+            }
         }
         CompData::Surround { .. } => todo!(), // surround
     }
@@ -313,4 +554,180 @@ mod tests {
             assert_eq!(assigns[1].total(), 0.2);
         }
     }
+
+    #[test]
+    fn test_gray_ctrl() {
+        let single = |name: &str, paths: Vec<KeyPath<usize, IndexSpace>>| {
+            let proto = crate::combination::StrucProto {
+                paths,
+                attrs: Default::default(),
+            };
+            let v = match proto.size().v {
+                0 => vec![],
+                _ => vec![AssignVal::new(0.1, 0.3)],
+            };
+            let mut comb = StrucComb::new_single(name.to_string(), proto);
+            if let CompData::Single { assigns, .. } = &mut comb.cdata {
+                *assigns = DataHV::new(vec![AssignVal::new(0.1, 0.3)], v);
+            }
+            comb
+        };
+        let left = single("一", vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]);
+        let right = single(
+            "口",
+            vec![KeyPath::from([
+                key_pos(0, 0),
+                key_pos(2, 0),
+                key_pos(2, 2),
+                key_pos(0, 2),
+                key_pos(0, 0),
+            ])],
+        );
+        let stroke = DataHV::new(1.0, 0.5);
+        assert!((left.get_ink_area(stroke) - 0.4).abs() < al::NORMAL_OFFSET);
+        assert!((right.get_ink_area(stroke) - 1.2).abs() < al::NORMAL_OFFSET);
+        assert!((right.get_gray(stroke) - 1.2 / 0.16).abs() < al::NORMAL_OFFSET);
+
+        let mut comb = StrucComb::new_complex(
+            "二".to_string(),
+            crate::construct::CstType::Scale(Axis::Horizontal),
+            vec![left, right],
+        );
+        ctrl_gray(&mut comb, &sj::json!({ "stroke": { "h": 1.0, "v": 0.5 } }));
+        let totals: Vec<f32> = ["0", "1"]
+            .iter()
+            .map(|p| {
+                comb.node(p)
+                    .unwrap()
+                    .get_assign_value(Axis::Horizontal, true)
+                    .total()
+            })
+            .collect();
+        assert!((totals[0] - 0.2).abs() < al::NORMAL_OFFSET, "{totals:?}");
+        assert!((totals[1] - 0.6).abs() < al::NORMAL_OFFSET, "{totals:?}");
+    }
+
+    #[test]
+    fn test_align_ctrl() {
+        // Horizontals at the boundaries of `v`.
+        let single = |v: &[f32]| {
+            let proto = crate::combination::StrucProto {
+                paths: (0..=v.len())
+                    .map(|y| KeyPath::from([key_pos(0, y), key_pos(1, y)]))
+                    .collect(),
+                attrs: Default::default(),
+            };
+            let mut comb = StrucComb::new_single("三".to_string(), proto);
+            if let CompData::Single { assigns, .. } = &mut comb.cdata {
+                *assigns = DataHV::new(
+                    vec![AssignVal::new(0.1, 0.1)],
+                    v.iter().map(|&v| AssignVal::new(0.1, v - 0.1)).collect(),
+                );
+            }
+            comb
+        };
+        let mut comb = StrucComb::new_complex(
+            "彡".to_string(),
+            crate::construct::CstType::Scale(Axis::Horizontal),
+            vec![
+                single(&[0.4, 0.6]),
+                single(&[0.44, 0.56]),
+                single(&[0.3, 0.7]),
+            ],
+        );
+
+        ctrl_align(&mut comb, &sj::json!({ "tolerance": { "v": 0.05 } }));
+        let lines: Vec<Vec<f32>> = ["0", "1", "2"]
+            .iter()
+            .map(|p| {
+                stroke_lines(comb.node(p).unwrap(), Axis::Vertical)
+                    .into_iter()
+                    .map(|line| line.pos)
+                    .collect()
+            })
+            .collect();
+        for (line, expect) in
+            lines
+                .iter()
+                .zip([[0.0, 0.42, 1.0], [0.0, 0.42, 1.0], [0.0, 0.3, 1.0]])
+        {
+            line.iter()
+                .zip(expect)
+                .for_each(|(a, b)| assert!((a - b).abs() < al::NORMAL_OFFSET, "{lines:?}"));
+        }
+        for p in ["0", "1", "2"] {
+            let total = DataHV::new(0.2, 1.0);
+            for axis in Axis::list() {
+                let val = comb.node(p).unwrap().get_assign_value(axis, false).total();
+                assert!((val - total.hv_get(axis)).abs() < al::NORMAL_OFFSET);
+            }
+        }
+
+        // Each line meets the others of its cluster, not only the one of its neighbour.
+        let mut comb = StrucComb::new_complex(
+            "彡".to_string(),
+            crate::construct::CstType::Scale(Axis::Horizontal),
+            vec![
+                single(&[0.4, 0.6]),
+                single(&[0.43, 0.57]),
+                single(&[0.46, 0.54]),
+            ],
+        );
+        ctrl_align(&mut comb, &sj::json!({ "tolerance": { "v": 0.07 } }));
+        for p in ["0", "1", "2"] {
+            let pos = stroke_lines(comb.node(p).unwrap(), Axis::Vertical)[1].pos;
+            assert!((pos - 0.43).abs() < al::NORMAL_OFFSET, "{p}: {pos}");
+        }
+
+        // The lines of `圭` in `挂` are at the offset of each `土`.
+        let gui = StrucComb::new_complex(
+            "圭".to_string(),
+            crate::construct::CstType::Scale(Axis::Vertical),
+            vec![single(&[0.22, 0.22]), single(&[0.28, 0.28])],
+        );
+        let mut comb = StrucComb::new_complex(
+            "挂".to_string(),
+            crate::construct::CstType::Scale(Axis::Horizontal),
+            vec![single(&[0.2, 0.25, 0.55]), gui],
+        );
+        ctrl_align(&mut comb, &sj::json!({ "tolerance": { "v": 0.05 } }));
+        let lines = |p: &str| -> Vec<(String, f32)> {
+            stroke_lines(comb.node(p).unwrap(), Axis::Vertical)
+                .into_iter()
+                .map(|line| (line.path, line.pos))
+                .collect()
+        };
+        let expect = |list: &[(&str, f32)]| -> Vec<(String, f32)> {
+            list.iter().map(|&(p, v)| (p.to_string(), v)).collect()
+        };
+        let near = |a: Vec<(String, f32)>, b: Vec<(String, f32)>| {
+            assert_eq!(a.len(), b.len(), "{a:?}");
+            a.iter().zip(b.iter()).for_each(|(a, b)| {
+                assert!(a.0 == b.0 && (a.1 - b.1).abs() < al::NORMAL_OFFSET, "{a:?}")
+            });
+        };
+        near(
+            lines("0"),
+            expect(&[("", 0.0), ("", 0.21), ("", 0.44), ("", 1.0)]),
+        );
+        near(
+            lines("1"),
+            expect(&[
+                ("0", 0.0),
+                ("0", 0.21),
+                ("0", 0.44),
+                ("1", 0.44),
+                ("1", 0.72),
+                ("1", 1.0),
+            ]),
+        );
+        for p in ["1.0", "1.1"] {
+            let val = comb
+                .node(p)
+                .unwrap()
+                .get_assign_value(Axis::Vertical, false);
+            let total = if p == "1.0" { 0.44 } else { 0.56 };
+            assert!((val.total() - total).abs() < al::NORMAL_OFFSET);
+        }
+    }
 }
@@ -0,0 +1,246 @@
+use super::{
+    Service, combination,
+    trace::{IntervalSource, TraceEvent, Tracer},
+};
+use crate::base::*;
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub const EXAMPLE_LIMIT: usize = 8;
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RuleUsage {
+    pub note: String,
+    // Number of characters in which the rule was applied.
+    pub count: usize,
+    // Number of characters in which the rule matched but an earlier rule was applied.
+    pub shadowed: usize,
+    pub examples: Vec<char>,
+}
+
+impl RuleUsage {
+    pub fn is_unused(&self) -> bool {
+        self.count == 0 && self.shadowed == 0
+    }
+
+    pub fn is_always_shadowed(&self) -> bool {
+        self.count == 0 && self.shadowed != 0
+    }
+}
+
+// Keys of `rules` are JSON pointers into the config, like `/interval/rules/3`.
+#[derive(Serialize, Default, Debug)]
+pub struct RuleStats {
+    pub chars: usize,
+    pub failed: BTreeMap<char, String>,
+    pub rules: BTreeMap<String, RuleUsage>,
+}
+
+impl RuleStats {
+    pub fn unused(&self) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|(_, usage)| usage.is_unused())
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    pub fn always_shadowed(&self) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|(_, usage)| usage.is_always_shadowed())
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+}
+
+fn trend_rule_id(axis: Axis, i: usize) -> String {
+    format!("/space_ctrls/trend/scale/{}/{i}", axis.symbol())
+}
+
+fn config_rules(service: &impl Service) -> BTreeMap<String, RuleUsage> {
+    let config = service.get_config();
+    let mut rules = BTreeMap::new();
+    let mut insert = |id: String, note: &str| {
+        rules.insert(
+            id,
+            RuleUsage {
+                note: note.to_string(),
+                ..Default::default()
+            },
+        );
+    };
+
+    for (i, rule) in config.get_interval_rules().iter().enumerate() {
+        insert(format!("/interval/rules/{i}"), &rule.note);
+    }
+    for (i, rule) in config.get_main_edge().iter().enumerate() {
+        insert(format!("/main_edge/{i}"), rule.note());
+    }
+    if let Some(trend) = config
+        .get_space_ctrls()
+        .1
+        .and_then(|settings| settings.get("trend"))
+        .and_then(|trend| trend.get("scale"))
+    {
+        for axis in Axis::list() {
+            if let Some(list) = trend.get(axis.symbol()).and_then(|list| list.as_array()) {
+                for (i, rule) in list.iter().enumerate() {
+                    let note = rule
+                        .get("note")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or_default();
+                    insert(trend_rule_id(axis, i), note);
+                }
+            }
+        }
+    }
+    for (tp, sections) in config.get_type_replace() {
+        for (section, names) in sections {
+            for (name, to) in names {
+                insert(format!("/type_replace/{tp}/{section:?}/{name}"), to);
+            }
+        }
+    }
+    for (name, list) in config.get_place_replace() {
+        for (i, (_, to)) in list.iter().enumerate() {
+            insert(format!("/place_replace/{name}/{i}"), to);
+        }
+    }
+
+    rules
+}
+
+// Rules applied and rules shadowed by an earlier one while generating a character.
+fn char_rules(service: &impl Service, chr: char) -> (Result<(), String>, [BTreeSet<String>; 2]) {
+    let mut tracer = Tracer::new();
+    let tree = combination::get_char_tree(service, chr.to_string(), &mut tracer);
+    let r = combination::gen_struc_comb(service, tree, &mut tracer)
+        .map(|_| ())
+        .map_err(|e| e.to_string());
+
+    let [mut applied, mut shadowed] = [BTreeSet::new(), BTreeSet::new()];
+    fn shadow<F>(ids: &mut BTreeSet<String>, rule: usize, candidates: &[usize], id: F)
+    where
+        F: Fn(usize) -> String,
+    {
+        ids.extend(candidates.iter().filter(|&&i| i > rule).map(|&i| id(i)));
+    }
+    for record in tracer.finish() {
+        match record.event {
+            TraceEvent::TypeReplace { tp, section, .. } => {
+                applied.insert(format!("/type_replace/{tp}/{section:?}/{}", record.comp));
+            }
+            TraceEvent::PlaceReplace {
+                rule, candidates, ..
+            } => {
+                let id = |i: usize| format!("/place_replace/{}/{i}", record.comp);
+                applied.insert(id(rule));
+                shadow(&mut shadowed, rule, &candidates, id);
+            }
+            TraceEvent::MainEdge { rule, .. } => {
+                applied.insert(format!("/main_edge/{rule}"));
+            }
+            TraceEvent::Trend { axis, rule } => {
+                applied.insert(trend_rule_id(axis, rule));
+            }
+            TraceEvent::Interval {
+                source:
+                    IntervalSource::Rule {
+                        index, candidates, ..
+                    },
+                ..
+            } => {
+                let id = |i: usize| format!("/interval/rules/{i}");
+                applied.insert(id(index));
+                shadow(&mut shadowed, index, &candidates, id);
+            }
+            _ => {}
+        }
+    }
+
+    (r, [applied, shadowed])
+}
+
+// Counts how often each rule of the config is used when generating `chars`.
+// Shadowing is only known for rule lists where the first match wins and all matches are traced:
+// `interval.rules` and `place_replace`.
+pub fn rule_stats(service: &impl Service, chars: &[char]) -> RuleStats {
+    let mut stats = RuleStats {
+        chars: chars.len(),
+        failed: Default::default(),
+        rules: config_rules(service),
+    };
+
+    for &chr in chars {
+        let (r, [applied, shadowed]) = char_rules(service, chr);
+        if let Err(e) = r {
+            stats.failed.insert(chr, e);
+        }
+        for id in shadowed.difference(&applied) {
+            stats.rules.entry(id.clone()).or_default().shadowed += 1;
+        }
+        for id in applied {
+            let usage = stats.rules.entry(id).or_default();
+            usage.count += 1;
+            if usage.examples.len() < EXAMPLE_LIMIT {
+                usage.examples.push(chr);
+            }
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combination::StrucProto,
+        construct::{CpAttrs, CstTable},
+        service::SimpleService,
+    };
+    use serde_json as sj;
+
+    #[test]
+    fn test_rule_stats() {
+        let mut table = CstTable::empty();
+        for name in ["一", "口"] {
+            table.insert(name.to_string(), CpAttrs::single());
+        }
+        table.insert(
+            "二".to_string(),
+            sj::from_value(sj::json!({ "tp": "⿰", "components": ["一", "口"] })).unwrap(),
+        );
+
+        let mut service = SimpleService::new(table);
+        service.config = sj::from_value(sj::json!({
+            "place_replace": {
+                "一": [["x", "一横"], ["*", "一竖"], ["o", "一点"]]
+            }
+        }))
+        .unwrap();
+        let proto = StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]);
+        for name in ["一", "一横", "口"] {
+            service.strucs.insert(name.to_string(), proto.clone());
+        }
+
+        let stats = rule_stats(&service, &['一', '二']);
+        assert_eq!(stats.chars, 2);
+        assert!(stats.failed.contains_key(&'二'));
+
+        let usage = &stats.rules["/place_replace/一/0"];
+        assert_eq!(usage.count, 1);
+        assert_eq!(usage.note, "一横");
+        assert_eq!(usage.examples, vec!['一']);
+
+        let usage = &stats.rules["/place_replace/一/1"];
+        assert_eq!(usage.count, 1);
+        assert_eq!(usage.shadowed, 1);
+        assert_eq!(stats.failed[&'二'], "`一竖` is empty!");
+
+        assert_eq!(stats.unused(), vec!["/place_replace/一/2"]);
+        assert!(stats.always_shadowed().is_empty());
+    }
+}
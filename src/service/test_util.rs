@@ -0,0 +1,79 @@
+use super::{LocalService, SimpleService, fas::FasFile};
+use crate::{
+    base::*,
+    combination::{CompTree, StrucProto},
+    construct::{CharTree, CpAttrs, CstTable, CstType},
+};
+use serde_json as sj;
+use std::path::Path;
+
+// `一` is a single horizontal stroke and `二` stacks two of them. `三` has no data.
+pub fn stacked_service() -> SimpleService {
+    let mut table = CstTable::empty();
+    table.insert("一".to_string(), CpAttrs::single());
+    table.insert(
+        "二".to_string(),
+        sj::from_value(sj::json!({ "tp": "⿱", "components": ["一", "一"] })).unwrap(),
+    );
+    let mut service = SimpleService::new(table);
+    service.strucs.insert(
+        "一".to_string(),
+        StrucProto::from(vec![KeyPath::from([key_pos(0, 0), key_pos(2, 0)])]),
+    );
+    service
+}
+
+// `丅` and `丄` share a prototype of a horizontal and a vertical stroke.
+pub fn paired_service() -> SimpleService {
+    let mut service = SimpleService::new(CstTable::empty());
+    let proto = StrucProto::from(vec![
+        KeyPath::from([key_pos(0, 0), key_pos(2, 0)]),
+        KeyPath::from([key_pos(1, 0), key_pos(1, 2)]),
+    ]);
+    service.strucs.insert("丅".to_string(), proto.clone());
+    service.strucs.insert("丄".to_string(), proto);
+    service
+}
+
+// `二` as `丅` and `丄` side by side, built from `paired_service`.
+pub fn paired_tree() -> CharTree {
+    CharTree {
+        name: "二".to_string(),
+        tp: CstType::Scale(Axis::Horizontal),
+        children: vec![
+            CharTree::new_single("丅".to_string()),
+            CharTree::new_single("丄".to_string()),
+        ],
+    }
+}
+
+// A service loaded from an empty fas file saved in `dir`.
+pub fn local_service(dir: &Path) -> LocalService {
+    let path = dir.join("regular.fas.json");
+    let path = path.to_str().unwrap();
+    FasFile::default().save_pretty(path).unwrap();
+    let mut service = LocalService::new(CstTable::empty());
+    service.load_file(path).unwrap();
+    service
+}
+
+// A leaf of a finished tree with the given strokes.
+pub fn leaf(name: &str, paths: Vec<Vec<(f32, f32)>>) -> CompTree {
+    CompTree {
+        name: name.to_string(),
+        tp: CstType::Single,
+        paths: paths
+            .into_iter()
+            .map(|path| {
+                path.into_iter()
+                    .map(|(x, y)| WorkKeyPoint::new(WorkPoint::new(x, y)))
+                    .collect()
+            })
+            .collect(),
+        children: vec![],
+        alloc_box: WorkBox::zero(),
+        ink_box: None,
+        blanks: Default::default(),
+        metrics: None,
+    }
+}
@@ -0,0 +1,193 @@
+use crate::base::*;
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "by")]
+pub enum IntervalSource {
+    // `interval_alloc` of the components
+    Alloc,
+    // The first matching rule in `interval.rules`, `candidates` are all the matching rules.
+    Rule {
+        index: usize,
+        note: String,
+        candidates: Vec<usize>,
+    },
+    Default,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event")]
+pub enum TraceEvent {
+    TypeReplace {
+        tp: char,
+        section: Section,
+        name: String,
+    },
+    PlaceReplace {
+        rule: usize,
+        name: String,
+        candidates: Vec<usize>,
+    },
+    InPlace {
+        adjacency: DataHV<[bool; 2]>,
+        rules: Vec<String>,
+    },
+    MainEdge {
+        axis: Axis,
+        rule: usize,
+    },
+    Interval {
+        axis: Axis,
+        next: String,
+        val: usize,
+        source: IntervalSource,
+    },
+    Reduce {
+        axis: Axis,
+        from: usize,
+        to: usize,
+    },
+    Replace {
+        axis: Axis,
+        name: String,
+    },
+    AxisSwap {
+        axis: Axis,
+    },
+    Level {
+        axis: Axis,
+        level: usize,
+        base_len: usize,
+        length: f32,
+    },
+    VisualCorr {
+        axis: Axis,
+        side: Side,
+        val: f32,
+    },
+    Assign {
+        axis: Axis,
+        level: usize,
+        unit: f32,
+    },
+    SpaceCtrl {
+        name: String,
+    },
+    Trend {
+        axis: Axis,
+        rule: usize,
+    },
+    // Recorded at the node of `combination::Asymmetry`.
+    Asymmetry {
+        axis: Axis,
+        other: Option<String>,
+    },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TraceRecord {
+    // Node path as taken by `StrucComb::node`, empty for the root.
+    pub node: String,
+    pub comp: String,
+    #[serde(flatten)]
+    pub event: TraceEvent,
+}
+
+// Records nothing unless created with `Tracer::new`.
+#[derive(Default)]
+pub struct Tracer {
+    records: Option<Vec<TraceRecord>>,
+    // Path of the node being processed, recorded paths are relative to it.
+    scope: String,
+}
+
+fn join_path(path: &mut String, node: &str) {
+    if !path.is_empty() && !node.is_empty() {
+        path.push('.');
+    }
+    path.push_str(node);
+}
+
+impl Tracer {
+    pub fn new() -> Self {
+        Self {
+            records: Some(vec![]),
+            scope: String::new(),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.records.is_some()
+    }
+
+    // Runs `f` with `node`, relative to the current node, as the current node.
+    pub fn within<T>(&mut self, node: &str, f: impl FnOnce(&mut Self) -> T) -> T {
+        let len = self.scope.len();
+        join_path(&mut self.scope, node);
+        let r = f(self);
+        self.scope.truncate(len);
+        r
+    }
+
+    pub fn record<F>(&mut self, node: &str, comp: &str, event: F)
+    where
+        F: FnOnce() -> TraceEvent,
+    {
+        if let Some(records) = &mut self.records {
+            let mut path = self.scope.clone();
+            join_path(&mut path, node);
+            records.push(TraceRecord {
+                node: path,
+                comp: comp.to_string(),
+                event: event(),
+            });
+        }
+    }
+
+    pub fn finish(self) -> Vec<TraceRecord> {
+        self.records.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracer() {
+        let mut tracer = Tracer::disabled();
+        tracer.record("", "一", || unreachable!());
+        assert!(tracer.finish().is_empty());
+
+        let mut tracer = Tracer::new();
+        tracer.record("", "二", || TraceEvent::AxisSwap {
+            axis: Axis::Vertical,
+        });
+        tracer.within("1", |tracer| {
+            tracer.within("0", |tracer| {
+                tracer.record("", "一", || TraceEvent::Reduce {
+                    axis: Axis::Horizontal,
+                    from: 2,
+                    to: 1,
+                })
+            });
+            tracer.record("2.0", "丨", || TraceEvent::SpaceCtrl {
+                name: "trend".to_string(),
+            });
+        });
+        let json = serde_json::to_value(tracer.finish()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "node": "", "comp": "二", "event": "AxisSwap", "axis": "Vertical" },
+                { "node": "1.0", "comp": "一", "event": "Reduce", "axis": "Horizontal", "from": 2, "to": 1 },
+                { "node": "1.2.0", "comp": "丨", "event": "SpaceCtrl", "name": "trend" },
+            ])
+        );
+    }
+}